@@ -1,43 +1,247 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::diff::{DiffLine, FileDiff};
-use crate::git::CommitEntry;
+use crossterm::event::KeyCode;
+use ratatui::layout::{Position, Rect};
+use serde::Deserialize;
+
+use crate::changes::ChangeMarks;
+use crate::check::Check;
+use crate::command;
+use crate::config::Settings;
+use crate::diff::{self, DiffLine, FileDiff};
+use crate::git::{
+    BlameLine, CommitDetail, CommitEntry, CommitOptions, FileStatus, LogQuery, LogSearch, RepoState,
+};
+use crate::graph::{self, GraphRow};
+use crate::history::History;
+use crate::hooks::Hooks;
+use crate::keymap::{Action, KeyMap, KeyPress, Resolution};
+use crate::patch::{ExportScope, Part};
+use crate::pathspec::PathFilter;
+use crate::search::{Matcher, Scope};
+use crate::theme::Theme;
+use crate::timeline::{Snapshot, Timeline};
+use crate::tree::{self, TreeEntry, TreeNode, TreeRow};
+
+/// Load the next page once the cursor is this close to the end.
+const LOG_PREFETCH: usize = 10;
+
+/// Two clicks on the same row within this interval open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Largest count prefix accepted, so a stray `99999j` can't stall the loop.
+const MAX_COUNT: usize = 9999;
 
 /// Which diff view is currently displayed.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DiffView {
+    #[default]
     Unstaged,
     Staged,
 }
 
+impl DiffView {
+    pub fn other(self) -> Self {
+        match self {
+            DiffView::Unstaged => DiffView::Staged,
+            DiffView::Staged => DiffView::Unstaged,
+        }
+    }
+}
+
+/// Whether the diff view scrolls to changes as they come in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Follow {
+    #[default]
+    Off,
+    On,
+    /// On, but suspended by scrolling by hand until toggled again.
+    Paused,
+}
+
+/// File diffs a search runs over, with the view they belong to.
+pub type SearchSource<'a> = (DiffView, &'a [FileDiff]);
+
 /// Input mode — determines how keystrokes are routed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
     Normal,
-    Search, // typing in the /? search bar
+    Search,        // typing in the /? search bar
+    HistorySearch, // typing a server-side commit log search
+    LogFilter,     // typing commit log filters (author=, since=, path= ...)
+    PathFilter,    // typing the live diff's path filter
+    Command,       // typing a `:` command
 }
 
 /// Which screen is currently visible.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Screen {
-    Diff,      // current staged/unstaged diff view
-    CommitLog, // list of recent commits
+    Diff,         // current staged/unstaged diff view
+    CommitLog,    // list of recent commits
+    CommitDetail, // metadata + diff of one commit from the log
+    Blame,        // per-line blame of one file
+    Commit,       // commit composer
+    Timeline,     // working tree snapshots taken this session
+}
+
+/// Which pane of the diff screen receives navigation keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Diff,
+    Sidebar,
 }
 
 /// Tracks the current search query, matches, and navigation cursor.
 #[derive(Debug, Clone, Default)]
 pub struct SearchState {
     pub query: String,
-    pub forward: bool,                       // true = /, false = ?
-    pub active: bool,                        // matches exist and are navigable
-    pub matches: Vec<(usize, usize, usize)>, // (line_idx, byte_start, byte_end)
+    pub forward: bool, // true = /, false = ?
+    pub active: bool,  // matches exist and are navigable
+    pub matches: Vec<SearchMatch>,
     pub current_match: usize,
+    /// Lines the search looks at, unless the query has a scope prefix.
+    pub scope: Scope,
+    /// Match the query as plain text instead of a regex.
+    pub literal: bool,
+    /// Why the query doesn't compile, shown in the prompt.
+    pub error: Option<String>,
+    /// Search the other diff view too, after the current one.
+    pub all_views: bool,
+    /// View and scroll position when the prompt opened, restored on cancel.
+    pub origin: (DiffView, u16),
+    /// Commit log entries the query matches, by index. The log searches
+    /// commits instead of `matches`.
+    pub log_matches: Vec<usize>,
+    /// Selected commit when the prompt opened in the log.
+    pub log_origin: usize,
+}
+
+/// A match located in the file diffs rather than in `visible_lines`, so
+/// it survives folding and can point into the other view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub view: DiffView,
+    pub file: String,
+    /// Line within the file's diff, `None` for the filename.
+    pub line: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SearchState {
+    /// Compile the query, or `None` when it's empty or invalid.
+    pub fn matcher(&self) -> Option<Matcher> {
+        Matcher::new(&self.query, self.literal, self.scope)
+            .ok()
+            .flatten()
+    }
+}
+
+/// Tab completion in the `:` prompt; repeated Tabs cycle `candidates`.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// Byte offset in the input where the completed word starts.
+    pub start: usize,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+/// A modal message box drawn over the current screen; any key dismisses it.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub title: String,
+    pub body: String,
+}
+
+/// Full-screen list of the current screen's key bindings.
+#[derive(Debug, Clone, Default)]
+pub struct HelpOverlay {
+    pub scroll: u16,
+    /// Only bindings containing this text are listed.
+    pub filter: String,
+    /// Whether keys are typed into `filter` rather than scrolling.
+    pub editing: bool,
+}
+
+/// Screen regions recorded while drawing, used to map mouse clicks.
+#[derive(Debug, Clone, Default)]
+pub struct ClickMap {
+    /// Inner area of the scrollable diff or list.
+    pub content: Rect,
+    /// Index of the line or item drawn on the first row of `content`.
+    pub first_item: usize,
+    /// Inner area of the file tree sidebar, if shown.
+    pub sidebar: Rect,
+    /// Index of the tree row drawn on the first row of `sidebar`.
+    pub sidebar_first_item: usize,
+    /// Clickable status and help bar segments.
+    pub buttons: Vec<(Rect, Action)>,
+}
+
+impl ClickMap {
+    /// Item index under the given terminal cell, if it is inside `content`.
+    pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        row_at(self.content, self.first_item, column, row)
+    }
+
+    /// Tree row index under the given terminal cell.
+    pub fn sidebar_item_at(&self, column: u16, row: u16) -> Option<usize> {
+        row_at(self.sidebar, self.sidebar_first_item, column, row)
+    }
+
+    /// Action of the bar segment under the given terminal cell.
+    pub fn button_at(&self, column: u16, row: u16) -> Option<Action> {
+        self.buttons
+            .iter()
+            .find(|(r, _)| r.contains(Position::new(column, row)))
+            .map(|&(_, action)| action)
+    }
+}
+
+fn row_at(area: Rect, first: usize, column: u16, row: u16) -> Option<usize> {
+    area.contains(Position::new(column, row))
+        .then(|| first + (row - area.y) as usize)
+}
+
+/// A commit being composed in the commit screen.
+#[derive(Debug, Clone)]
+pub struct CommitDraft {
+    pub options: CommitOptions,
+    /// Message with comment lines stripped.
+    pub message: String,
+    /// File handed to the editor and to `git commit -F`.
+    pub message_file: PathBuf,
+}
+
+/// One file being blamed at one revision.
+#[derive(Debug, Clone)]
+pub struct BlameView {
+    pub path: String,
+    /// Revision being blamed — `None` for the working tree.
+    pub rev: Option<String>,
+    pub lines: Vec<BlameLine>,
+    /// Cursor position in `lines`.
+    pub selected: usize,
+}
+
+/// Optional commit log columns, toggled from the log screen.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogColumns {
+    pub full_hash: bool,
+    pub absolute_date: bool,
+    pub refs: bool,
+    pub graph: bool,
 }
 
 /// Central application state — owned exclusively by the main thread.
 pub struct App {
     /// Whether the app should exit on the next loop iteration.
     pub should_quit: bool,
+    /// Colours used by every screen.
+    pub theme: Theme,
     /// Current diff view (staged vs unstaged).
     pub view: DiffView,
     /// Vertical scroll offset (in lines) into the diff output.
@@ -46,6 +250,29 @@ pub struct App {
     pub diff_line_count: u16,
     /// Height of the diff viewport in terminal rows (set each render).
     pub viewport_height: u16,
+    /// Paths the live diff and watcher are restricted to.
+    pub path_filter: PathFilter,
+    /// Text typed into the path filter prompt.
+    pub path_filter_input: String,
+    /// When set, the main loop re-queries git before the next frame.
+    pub refresh: bool,
+    /// Ref the diffs compare against instead of HEAD and the index.
+    pub base: Option<String>,
+    /// Lines and files recent refreshes brought in.
+    pub changes: ChangeMarks,
+    /// How long `changes` stay highlighted; zero disables tracking.
+    pub change_ttl: Duration,
+    /// Follow mode of the diff screen.
+    pub follow: Follow,
+    /// Working tree snapshots taken at refreshes.
+    pub timeline: Timeline,
+    /// Id of the snapshot selected on the timeline screen.
+    pub timeline_selected: usize,
+    /// Id of the snapshot the selected one is compared with; `None`
+    /// compares it with the one before.
+    pub timeline_mark: Option<usize>,
+    /// Diff between the compared snapshots.
+    pub timeline_diff: Vec<FileDiff>,
 
     /// Current screen being displayed.
     pub screen: Screen,
@@ -53,15 +280,77 @@ pub struct App {
     pub input_mode: InputMode,
     /// Search state.
     pub search: SearchState,
+    /// Earlier search queries.
+    pub search_history: History,
+    /// Text typed into the `:` prompt.
+    pub command_input: String,
+    /// Earlier `:` commands.
+    pub command_history: History,
+    /// Active Tab completion in the `:` prompt.
+    pub completion: Option<Completion>,
+    /// Normal-mode key bindings.
+    pub keymap: KeyMap,
+    /// Keys of a multi-key binding typed so far, e.g. the first `g` of `gg`.
+    pub pending_keys: Vec<KeyPress>,
+    /// Count prefix typed so far, e.g. `5` of `5j`.
+    pub pending_count: Option<usize>,
 
     /// Recent commits from `git log`.
     pub commit_log: Vec<CommitEntry>,
     /// Cursor position in the commit log list.
     pub commit_log_selected: usize,
+    /// Query used for the loaded commit log pages.
+    pub log_query: LogQuery,
+    /// Set once `git log` returned a short page — no more history to load.
+    pub commit_log_exhausted: bool,
+    /// Which optional columns the commit log shows.
+    pub log_columns: LogColumns,
+    /// Lane layout for `commit_log`, one row per commit.
+    pub commit_graph: Vec<GraphRow>,
+    /// Text typed into the history search prompt.
+    pub history_query: String,
+    /// Text typed into the log filter prompt.
+    pub log_filter_input: String,
+    /// Commit currently open in the detail screen.
+    pub commit_detail: Option<CommitDetail>,
+    /// Screen to return to when the detail screen is closed.
+    detail_return: Screen,
+    /// Blame views, innermost last. Re-blaming at a parent pushes a view,
+    /// going back pops it.
+    pub blame_stack: Vec<BlameView>,
+    /// Diff view scroll and fold state, saved while the detail screen
+    /// borrows `visible_lines` and `collapsed`.
+    diff_stash: Option<(u16, HashSet<String>)>,
 
     /// When set, the main loop should suspend the TUI and pipe this
     /// content to the user's pager.
     pub pager_content: Option<String>,
+    /// When set, the main loop should suspend the TUI and open this file
+    /// in the user's editor.
+    pub editor_request: Option<PathBuf>,
+    /// Transient message shown in the help bar until the next key press.
+    pub message: Option<String>,
+    /// Modal popup, e.g. a failed commit hook's output.
+    pub popup: Option<Popup>,
+    /// Key binding help drawn over the current screen.
+    pub help: Option<HelpOverlay>,
+    /// Whether mouse events are captured; off leaves text selection to
+    /// the terminal.
+    pub mouse: bool,
+    /// Whether the file tree sidebar is shown on the diff screen.
+    pub sidebar: bool,
+    /// Pane that receives navigation keys while the sidebar is shown.
+    pub focus: Pane,
+    /// Directories folded in the file tree.
+    pub tree_collapsed: HashSet<String>,
+    /// Cursor row in the file tree.
+    pub tree_selected: usize,
+    /// Click targets from the last frame.
+    pub click_map: ClickMap,
+    /// Row and time of the last click, for double-click detection.
+    last_click: Option<(usize, Instant)>,
+    /// Commit being composed.
+    pub commit_draft: Option<CommitDraft>,
 
     /// Filenames whose sections are currently collapsed.
     pub collapsed: HashSet<String>,
@@ -69,34 +358,99 @@ pub struct App {
     pub visible_lines: Vec<DiffLine>,
     /// Indices into `visible_lines` where FileHeader lines appear.
     pub file_header_positions: Vec<usize>,
+    /// Visual line selection in `visible_lines`: where it started and the
+    /// cursor end.
+    pub selection: Option<(usize, usize)>,
+    /// When set, the main loop copies this to the clipboard.
+    pub clipboard: Option<String>,
+
+    /// Configured hook commands and their latest runs.
+    pub hooks: Hooks,
+    /// Show the output of the latest hook run below the diff.
+    pub hook_panel: bool,
+    /// The check run after every change and its latest result.
+    pub check: Check,
+    /// Show the check output below the diff.
+    pub check_panel: bool,
+    /// When set, the main loop starts a check run.
+    pub run_check: bool,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
         Self {
             should_quit: false,
-            view: DiffView::Unstaged,
+            theme: settings.theme.clone(),
+            view: settings.default_view,
             scroll: 0,
             diff_line_count: 0,
             viewport_height: 0,
+            // Validated when the settings were loaded
+            path_filter: PathFilter::new(settings.paths.clone()).unwrap_or_default(),
+            path_filter_input: String::new(),
+            refresh: false,
+            base: None,
+            changes: ChangeMarks::default(),
+            change_ttl: Duration::from_secs(settings.highlight_changes_secs),
+            follow: Follow::Off,
+            timeline: Timeline::new(settings.timeline_size),
+            timeline_selected: 0,
+            timeline_mark: None,
+            timeline_diff: Vec::new(),
             screen: Screen::Diff,
             input_mode: InputMode::Normal,
             search: SearchState::default(),
+            search_history: History::default(),
+            command_input: String::new(),
+            command_history: History::default(),
+            completion: None,
+            keymap: settings.keymap.clone(),
+            pending_keys: Vec::new(),
+            pending_count: None,
             commit_log: Vec::new(),
             commit_log_selected: 0,
+            log_query: LogQuery {
+                count: settings.log_page_size,
+                ..LogQuery::default()
+            },
+            commit_log_exhausted: false,
+            log_columns: LogColumns::default(),
+            commit_graph: Vec::new(),
+            history_query: String::new(),
+            log_filter_input: String::new(),
+            commit_detail: None,
+            detail_return: Screen::CommitLog,
+            blame_stack: Vec::new(),
+            diff_stash: None,
             pager_content: None,
+            editor_request: None,
+            message: None,
+            popup: None,
+            help: None,
+            mouse: settings.mouse,
+            sidebar: settings.sidebar,
+            focus: Pane::Diff,
+            tree_collapsed: HashSet::new(),
+            tree_selected: 0,
+            click_map: ClickMap::default(),
+            last_click: None,
+            commit_draft: None,
             collapsed: HashSet::new(),
             visible_lines: Vec::new(),
             file_header_positions: Vec::new(),
+            selection: None,
+            clipboard: None,
+            hooks: Hooks::new(&settings.hooks),
+            hook_panel: false,
+            check: Check::new(settings.check.as_ref()),
+            check_panel: false,
+            run_check: settings.check.is_some(),
         }
     }
 
     /// Toggle between staged and unstaged views, resetting scroll.
     pub fn toggle_view(&mut self) {
-        self.view = match self.view {
-            DiffView::Unstaged => DiffView::Staged,
-            DiffView::Staged => DiffView::Unstaged,
-        };
+        self.view = self.view.other();
         self.scroll = 0;
         self.clear_search();
     }
@@ -138,12 +492,71 @@ impl App {
         self.diff_line_count.saturating_sub(self.viewport_height)
     }
 
+    // ── Key sequences ───────────────────────────────────────────
+
+    /// Feed a Normal-mode key through the keymap. Returns the action and
+    /// how often to run it once a binding is complete.
+    ///
+    /// Digits not bound on the current screen build up a count prefix;
+    /// `0` only counts after another digit.
+    pub fn feed_key(&mut self, key: KeyPress) -> Option<(Action, usize)> {
+        if let KeyCode::Char(c @ '0'..='9') = key.code {
+            let starts_count = c != '0' || self.pending_count.is_some();
+            if self.pending_keys.is_empty()
+                && key.modifiers.is_empty()
+                && starts_count
+                && self.keymap.resolve(self.screen, &[key]) == Resolution::NoMatch
+            {
+                let digit = c as usize - '0' as usize;
+                let count = self.pending_count.unwrap_or(0) * 10 + digit;
+                self.pending_count = Some(count.min(MAX_COUNT));
+                return None;
+            }
+        }
+
+        self.pending_keys.push(key);
+        match self.keymap.resolve(self.screen, &self.pending_keys) {
+            Resolution::Pending => None,
+            Resolution::Action(action) => {
+                let count = self.pending_count.take().unwrap_or(1);
+                self.pending_keys.clear();
+                Some((action, if action.repeatable() { count } else { 1 }))
+            }
+            Resolution::NoMatch => {
+                self.clear_pending_keys();
+                None
+            }
+        }
+    }
+
+    /// Drop a half-typed key sequence and count.
+    pub fn clear_pending_keys(&mut self) {
+        self.pending_keys.clear();
+        self.pending_count = None;
+    }
+
+    // ── Mouse ───────────────────────────────────────────────────
+
+    /// Record a click on `item`; returns `true` if it completes a
+    /// double-click on the same item.
+    pub fn register_click(&mut self, item: usize) -> bool {
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .is_some_and(|(prev, at)| prev == item && now.duration_since(at) <= DOUBLE_CLICK);
+        // A third click starts a new pair
+        self.last_click = if double { None } else { Some((item, now)) };
+        double
+    }
+
     // ── File sections (collapse / navigation) ──────────────────
 
     /// Rebuild `visible_lines` and `file_header_positions` from structured file diffs.
     pub fn recompute_visible_lines(&mut self, files: &[FileDiff]) {
         self.visible_lines.clear();
         self.file_header_positions.clear();
+        // Positions in the old lines mean nothing in the new ones
+        self.selection = None;
 
         for fd in files {
             // Skip empty-filename entries (e.g. from RepoState::empty)
@@ -192,15 +605,29 @@ impl App {
     /// Toggle the collapsed state of the file under the current viewport position.
     pub fn toggle_file_fold(&mut self, files: &[FileDiff]) {
         if let Some(name) = self.file_at_scroll() {
-            if self.collapsed.contains(&name) {
-                self.collapsed.remove(&name);
-            } else {
-                self.collapsed.insert(name);
+            self.toggle_fold(name, files);
+        }
+    }
+
+    /// Toggle the fold of the file whose header is at `line`. Returns
+    /// `false` if that line is not a file header.
+    pub fn toggle_fold_at(&mut self, line: usize, files: &[FileDiff]) -> bool {
+        match self.visible_lines.get(line) {
+            Some(DiffLine::FileHeader { filename, .. }) => {
+                self.toggle_fold(filename.clone(), files);
+                true
             }
-            self.recompute_visible_lines(files);
+            _ => false,
         }
     }
 
+    fn toggle_fold(&mut self, name: String, files: &[FileDiff]) {
+        if !self.collapsed.remove(&name) {
+            self.collapsed.insert(name);
+        }
+        self.recompute_visible_lines(files);
+    }
+
     /// Collapse all file sections.
     pub fn fold_all(&mut self, files: &[FileDiff]) {
         for fd in files {
@@ -217,8 +644,18 @@ impl App {
         self.recompute_visible_lines(files);
     }
 
+    /// Scroll so the header of `filename` is at the top, if it is shown.
+    pub fn jump_to_file(&mut self, filename: &str) {
+        let pos = self.file_header_positions.iter().find(|&&p| {
+            matches!(&self.visible_lines[p], DiffLine::FileHeader { filename: f, .. } if f == filename)
+        });
+        if let Some(&pos) = pos {
+            self.scroll = (pos as u16).min(self.max_scroll());
+        }
+    }
+
     /// Determine which file the current scroll position is inside of.
-    fn file_at_scroll(&self) -> Option<String> {
+    pub fn file_at_scroll(&self) -> Option<String> {
         let pos = self.scroll as usize;
         // Find the last file header at or before the scroll position
         let header_idx = self.file_header_positions.iter().rposition(|&p| p <= pos)?;
@@ -230,6 +667,345 @@ impl App {
         }
     }
 
+    // ── Visual selection and export ─────────────────────────────
+
+    /// Start selecting lines at the top of the viewport, or stop.
+    pub fn toggle_selection(&mut self) {
+        self.selection = match self.selection {
+            Some(_) => None,
+            None if self.visible_lines.is_empty() => None,
+            None => Some((self.scroll as usize, self.scroll as usize)),
+        };
+    }
+
+    /// Move the selection's cursor end by `delta` lines, scrolling to keep
+    /// it on screen.
+    pub fn move_selection(&mut self, delta: isize) {
+        let Some((anchor, cursor)) = self.selection else {
+            return;
+        };
+        let last = self.visible_lines.len().saturating_sub(1);
+        let cursor = cursor.saturating_add_signed(delta).min(last);
+        self.selection = Some((anchor, cursor));
+        let height = self.viewport_height.max(1) as usize;
+        if cursor < self.scroll as usize {
+            self.scroll = cursor as u16;
+        } else if cursor >= self.scroll as usize + height {
+            self.scroll = (cursor + 1 - height) as u16;
+        }
+    }
+
+    /// First and last selected line, in order.
+    pub fn selection_range(&self) -> Option<(usize, usize)> {
+        self.selection.map(|(a, b)| (a.min(b), a.max(b)))
+    }
+
+    /// What an export without an explicit scope covers.
+    pub fn default_export_scope(&self) -> ExportScope {
+        if self.selection.is_some() {
+            ExportScope::Selection
+        } else {
+            ExportScope::View
+        }
+    }
+
+    /// Parts of `files`, the diff shown on screen, that `scope` covers.
+    /// The cursor is the selection's cursor end, or else the top line.
+    pub fn export_parts<'a>(
+        &self,
+        scope: ExportScope,
+        files: &'a [FileDiff],
+    ) -> Vec<(&'a FileDiff, Part)> {
+        let cursor = self.selection.map_or(self.scroll as usize, |(_, c)| c);
+        let find = |name: &str| files.iter().find(|fd| fd.filename == name);
+        match scope {
+            ExportScope::View => files.iter().map(|fd| (fd, Part::Whole)).collect(),
+            ExportScope::File => self
+                .locate(cursor)
+                .and_then(|(name, _)| find(name))
+                .map(|fd| (fd, Part::Whole))
+                .into_iter()
+                .collect(),
+            ExportScope::Hunk => {
+                let Some((name, line)) = self.locate(cursor) else {
+                    return Vec::new();
+                };
+                let Some(fd) = find(name) else {
+                    return Vec::new();
+                };
+                let is_hunk = |dl: &DiffLine| matches!(dl, DiffLine::Hunk(_));
+                // Above the first hunk, take the first one
+                let hunk = line
+                    .and_then(|l| fd.lines[..=l].iter().rposition(is_hunk))
+                    .or_else(|| fd.lines.iter().position(is_hunk));
+                hunk.map(|h| (fd, Part::Hunk(h))).into_iter().collect()
+            }
+            ExportScope::Selection => {
+                let Some((first, last)) = self.selection_range() else {
+                    return Vec::new();
+                };
+                let mut parts: Vec<(&FileDiff, Part)> = Vec::new();
+                for pos in first..=last {
+                    let Some((name, line)) = self.locate(pos) else {
+                        continue;
+                    };
+                    let Some(fd) = find(name) else {
+                        continue;
+                    };
+                    let part = match line {
+                        Some(l) => Part::Lines(l, l),
+                        // A folded file counts as a whole
+                        None if self.collapsed.contains(name) => Part::Whole,
+                        None => continue,
+                    };
+                    match parts.last_mut() {
+                        Some((prev, Part::Lines(_, end))) if prev.filename == fd.filename => {
+                            if let Part::Lines(_, l) = part {
+                                *end = l;
+                            }
+                        }
+                        _ => parts.push((fd, part)),
+                    }
+                }
+                parts
+            }
+        }
+    }
+
+    /// File and diff line shown at `pos` of `visible_lines`; the line is
+    /// `None` on the file's header.
+    fn locate(&self, pos: usize) -> Option<(&str, Option<usize>)> {
+        let header = *self.file_header_positions.iter().rfind(|&&p| p <= pos)?;
+        match &self.visible_lines[header] {
+            DiffLine::FileHeader { filename, .. } => {
+                Some((filename, (pos > header).then(|| pos - header - 1)))
+            }
+            _ => None,
+        }
+    }
+
+    // ── Change highlights ───────────────────────────────────────
+
+    /// Carry change marks from `old` to `new`; with `mark`, also mark what
+    /// `new` brought in.
+    pub fn track_changes(&mut self, old: &RepoState, new: &RepoState, mark: bool) {
+        if self.change_ttl.is_zero() {
+            return;
+        }
+        let now = mark.then(Instant::now);
+        let ttl = self.change_ttl;
+        self.changes.update(
+            DiffView::Unstaged,
+            &old.unstaged_diff,
+            &new.unstaged_diff,
+            now,
+            ttl,
+        );
+        self.changes.update(
+            DiffView::Staged,
+            &old.staged_diff,
+            &new.staged_diff,
+            now,
+            ttl,
+        );
+    }
+
+    /// Scroll to the newest change in the current view, unfolding its
+    /// file. Returns `false` if nothing is marked.
+    pub fn jump_to_latest_change(&mut self, files: &[FileDiff]) -> bool {
+        let Some((file, line)) = self.changes.latest(self.view) else {
+            return false;
+        };
+        let file = file.to_string();
+        self.scroll_to_change(&file, line, files);
+        true
+    }
+
+    // ── Follow mode ─────────────────────────────────────────────
+
+    /// Turn follow mode on or off; a paused follow is resumed and catches
+    /// up with the latest change.
+    pub fn toggle_follow(&mut self, files: &[FileDiff]) {
+        self.follow = match self.follow {
+            Follow::On => Follow::Off,
+            Follow::Off | Follow::Paused => {
+                self.jump_to_latest_change(files);
+                Follow::On
+            }
+        };
+    }
+
+    /// Suspend follow mode, e.g. when scrolling by hand.
+    pub fn pause_follow(&mut self) {
+        if self.follow == Follow::On {
+            self.follow = Follow::Paused;
+        }
+    }
+
+    /// Scroll to what a refresh at `since` changed. `paths` are the files
+    /// the watcher reported, most recently modified first; they win over
+    /// other files the refresh changed.
+    pub fn follow_change(&mut self, paths: &[PathBuf], since: Instant, files: &[FileDiff]) {
+        if self.follow != Follow::On {
+            return;
+        }
+        let reported = paths.iter().map(|p| p.to_string_lossy().into_owned());
+        let others = files.iter().map(|fd| fd.filename.clone());
+        for file in reported.chain(others) {
+            if let Some(line) = self.changes.latest_since(self.view, &file, since) {
+                self.scroll_to_change(&file, line, files);
+                return;
+            }
+        }
+        // Without change tracking, the file is as close as it gets
+        if self.change_ttl.is_zero() {
+            if let Some(file) = paths.first() {
+                self.jump_to_file(&file.to_string_lossy());
+            }
+        }
+    }
+
+    /// Scroll to line `line` of `file`'s diff, unfolding the file. Its hunk
+    /// header stays on screen when both fit.
+    fn scroll_to_change(&mut self, file: &str, line: usize, files: &[FileDiff]) {
+        if self.collapsed.remove(file) {
+            self.recompute_visible_lines(files);
+        }
+        let Some(&header) = self.header_lines().get(file) else {
+            return;
+        };
+        let hunk = files
+            .iter()
+            .find(|fd| fd.filename == file)
+            .and_then(|fd| {
+                fd.lines
+                    .get(..=line)?
+                    .iter()
+                    .rposition(|dl| matches!(dl, DiffLine::Hunk(_)))
+            })
+            .filter(|&hunk| line - hunk + 2 < self.viewport_height as usize);
+        let top = match hunk {
+            Some(hunk) => header + 1 + hunk,
+            None => (header + 1 + line).saturating_sub(5),
+        };
+        self.scroll = (top as u16).min(self.max_scroll());
+    }
+
+    /// Return to the diff screen from whichever screen is open. A commit
+    /// message draft is kept.
+    ///
+    /// The caller must recompute `visible_lines` for the diff view.
+    pub fn show_diff(&mut self) {
+        match self.screen {
+            Screen::Diff => return,
+            Screen::CommitDetail => {
+                self.close_commit_detail();
+                // Leave the log or blame it was opened from as well
+                match self.screen {
+                    Screen::Blame => self.close_blame(),
+                    _ => self.screen = Screen::Diff,
+                }
+            }
+            Screen::Timeline => self.close_timeline(),
+            Screen::Blame => self.close_blame(),
+            Screen::CommitLog | Screen::Commit => self.screen = Screen::Diff,
+        }
+        self.clear_search();
+    }
+
+    /// Scroll to the diff line showing line `line` of `file` in the new
+    /// version, or to its closest hunk. Returns `false` if the file is not
+    /// in the diff.
+    pub fn jump_to_line(&mut self, file: &str, line: usize, files: &[FileDiff]) -> bool {
+        let Some(index) = files
+            .iter()
+            .find(|fd| fd.filename == file)
+            .and_then(|fd| diff::new_line_index(fd, line))
+        else {
+            return false;
+        };
+        self.pause_follow();
+        self.scroll_to_change(file, index, files);
+        true
+    }
+
+    // ── File tree sidebar ───────────────────────────────────────
+
+    /// Changed files of the current view as tree rows. `files` supplies
+    /// the `+/-` stats.
+    pub fn file_tree(&self, statuses: &[FileStatus], files: &[FileDiff]) -> Vec<TreeRow> {
+        let mut entries: Vec<TreeEntry> = statuses
+            .iter()
+            .filter_map(|st| {
+                let status = match self.view {
+                    DiffView::Unstaged => st.worktree,
+                    DiffView::Staged => st.index,
+                };
+                if status == ' ' || (self.view == DiffView::Staged && status == '?') {
+                    return None;
+                }
+                let stats = files
+                    .iter()
+                    .find(|fd| fd.filename == st.path)
+                    .map(|fd| (fd.added, fd.removed));
+                Some(TreeEntry {
+                    path: st.path.clone(),
+                    status,
+                    stats,
+                })
+            })
+            .collect();
+        // Against a base ref, committed changes show in the diff but not in
+        // `git status`
+        if self.base.is_some() {
+            for fd in files {
+                if !fd.filename.is_empty() && !entries.iter().any(|e| e.path == fd.filename) {
+                    entries.push(TreeEntry {
+                        path: fd.filename.clone(),
+                        status: 'M',
+                        stats: Some((fd.added, fd.removed)),
+                    });
+                }
+            }
+        }
+        tree::build(&entries, &self.tree_collapsed)
+    }
+
+    /// Show or hide the sidebar; showing it gives it focus.
+    pub fn toggle_sidebar(&mut self) {
+        self.sidebar = !self.sidebar;
+        self.focus = if self.sidebar {
+            Pane::Sidebar
+        } else {
+            Pane::Diff
+        };
+    }
+
+    /// Move the tree cursor by `delta` rows and scroll the diff to the
+    /// selected file.
+    pub fn tree_move(&mut self, delta: isize, rows: &[TreeRow]) {
+        if rows.is_empty() {
+            return;
+        }
+        let last = rows.len() - 1;
+        self.tree_selected = self.tree_selected.saturating_add_signed(delta).min(last);
+        if let Some(path) = rows[self.tree_selected].file_path() {
+            self.jump_to_file(path);
+        }
+    }
+
+    /// Fold or unfold the directory at tree row `row`. Returns `false` for
+    /// file rows.
+    pub fn toggle_tree_dir(&mut self, row: &TreeRow) -> bool {
+        let TreeNode::Dir { path, .. } = &row.node else {
+            return false;
+        };
+        if !self.tree_collapsed.remove(path) {
+            self.tree_collapsed.insert(path.clone());
+        }
+        true
+    }
+
     // ── Commit log navigation ───────────────────────────────────
 
     pub fn commit_log_down(&mut self) {
@@ -243,51 +1019,473 @@ impl App {
         self.commit_log_selected = self.commit_log_selected.saturating_sub(1);
     }
 
+    /// Replace the commit log with the first page of a new query.
+    pub fn reset_commit_log(&mut self, page: Vec<CommitEntry>) {
+        self.commit_log_exhausted = page.len() < self.log_query.count;
+        self.commit_log = page;
+        self.commit_log_selected = 0;
+        self.log_query.skip = self.commit_log.len();
+        self.commit_graph = graph::build(&self.commit_log, self.log_query.skips_parents());
+    }
+
+    /// Append the next page of commits.
+    pub fn append_commit_log(&mut self, page: Vec<CommitEntry>) {
+        self.commit_log_exhausted = page.len() < self.log_query.count;
+        self.commit_log.extend(page);
+        self.log_query.skip = self.commit_log.len();
+        self.commit_graph = graph::build(&self.commit_log, self.log_query.skips_parents());
+        if !self.search.query.is_empty() {
+            self.recompute_matches(&[]);
+        }
+    }
+
+    /// Whether the cursor is close enough to the end to fetch another page.
+    pub fn commit_log_wants_more(&self) -> bool {
+        !self.commit_log_exhausted
+            && self.commit_log_selected + LOG_PREFETCH >= self.commit_log.len()
+    }
+
+    /// Toggle the graph column. Graph mode walks commits in topological
+    /// order, so the caller must reload the log.
+    pub fn toggle_commit_graph(&mut self) {
+        self.log_columns.graph = !self.log_columns.graph;
+        self.log_query.topo_order = self.log_columns.graph;
+    }
+
+    /// Open the log filter prompt, pre-filled with the active filter.
+    pub fn enter_log_filter(&mut self) {
+        self.input_mode = InputMode::LogFilter;
+        self.log_filter_input = self.log_query.filter.to_string();
+    }
+
+    /// Open the diff path filter prompt, pre-filled with the active filter.
+    pub fn enter_path_filter(&mut self) {
+        self.input_mode = InputMode::PathFilter;
+        self.path_filter_input = self.path_filter.to_string();
+    }
+
+    // ── Command prompt ──────────────────────────────────────────
+
+    pub fn enter_command(&mut self) {
+        self.input_mode = InputMode::Command;
+        self.command_input.clear();
+        self.command_history.reset();
+        self.completion = None;
+    }
+
+    pub fn command_push(&mut self, c: char) {
+        self.command_input.push(c);
+        self.command_history.reset();
+        self.completion = None;
+    }
+
+    pub fn command_pop(&mut self) {
+        self.command_input.pop();
+        self.command_history.reset();
+        self.completion = None;
+    }
+
+    /// Replace the input with an older (`older`) or newer history entry.
+    pub fn command_browse(&mut self, older: bool) {
+        let entry = if older {
+            self.command_history.older(&self.command_input)
+        } else {
+            self.command_history.newer()
+        };
+        if let Some(entry) = entry {
+            self.command_input = entry.to_string();
+            self.completion = None;
+        }
+    }
+
+    /// Complete the last word of the input; repeated calls cycle through
+    /// the candidates.
+    pub fn command_complete(&mut self, refs: &[String], root: &Path) {
+        match &mut self.completion {
+            Some(c) => c.index = (c.index + 1) % c.candidates.len(),
+            None => {
+                let (start, candidates) = command::complete(&self.command_input, refs, root);
+                if candidates.is_empty() {
+                    return;
+                }
+                self.completion = Some(Completion {
+                    start,
+                    candidates,
+                    index: 0,
+                });
+            }
+        }
+        if let Some(c) = &self.completion {
+            self.command_input.truncate(c.start);
+            self.command_input.push_str(&c.candidates[c.index]);
+        }
+    }
+
+    /// Leave the prompt, recording the command in the history.
+    pub fn command_finish(&mut self) -> String {
+        self.input_mode = InputMode::Normal;
+        self.completion = None;
+        let input = std::mem::take(&mut self.command_input);
+        self.command_history.push(&input);
+        input
+    }
+
+    /// Open the history search prompt, pre-filled with the active search.
+    pub fn enter_history_search(&mut self) {
+        self.input_mode = InputMode::HistorySearch;
+        self.history_query = match &self.log_query.search {
+            Some(LogSearch::Message(s)) => s.clone(),
+            Some(LogSearch::Author(s)) => format!("author:{s}"),
+            Some(LogSearch::Pickaxe(s)) => format!("code:{s}"),
+            None => String::new(),
+        };
+    }
+
+    // ── Commit detail ───────────────────────────────────────────
+
+    /// Show `detail` in the commit detail screen.
+    ///
+    /// The diff view's scroll and fold state is stashed and restored by
+    /// `close_commit_detail`, since both screens share `visible_lines`.
+    pub fn open_commit_detail(&mut self, detail: CommitDetail) {
+        if self.diff_stash.is_none() {
+            self.diff_stash = Some((self.scroll, std::mem::take(&mut self.collapsed)));
+        }
+        self.collapsed.clear();
+        self.scroll = 0;
+        self.recompute_visible_lines(&detail.files);
+        self.commit_detail = Some(detail);
+        self.detail_return = self.screen;
+        self.screen = Screen::CommitDetail;
+        self.clear_search();
+    }
+
+    /// Leave the commit detail screen and return to the screen it was
+    /// opened from.
+    ///
+    /// The caller must recompute `visible_lines` for the diff view.
+    pub fn close_commit_detail(&mut self) {
+        self.commit_detail = None;
+        if let Some((scroll, collapsed)) = self.diff_stash.take() {
+            self.scroll = scroll;
+            self.collapsed = collapsed;
+        }
+        self.screen = self.detail_return;
+        self.clear_search();
+    }
+
+    /// File diffs shown by the commit detail or timeline screen.
+    pub fn detail_files(&self) -> &[FileDiff] {
+        if self.screen == Screen::Timeline {
+            return &self.timeline_diff;
+        }
+        self.commit_detail.as_ref().map_or(&[], |d| &d.files)
+    }
+
+    /// Call `f` with the app and `detail_files`, which are moved out of
+    /// the app meanwhile so `f` can change it without copying them.
+    pub fn with_detail_files<R>(&mut self, f: impl FnOnce(&mut Self, &[FileDiff]) -> R) -> R {
+        let timeline = self.screen == Screen::Timeline;
+        let files = if timeline {
+            std::mem::take(&mut self.timeline_diff)
+        } else {
+            self.commit_detail
+                .as_mut()
+                .map(|d| std::mem::take(&mut d.files))
+                .unwrap_or_default()
+        };
+        let result = f(self, &files);
+        if timeline {
+            self.timeline_diff = files;
+        } else if let Some(detail) = &mut self.commit_detail {
+            detail.files = files;
+        }
+        result
+    }
+
+    // ── Timeline ────────────────────────────────────────────────
+
+    /// Show the timeline with the latest snapshot selected. Returns
+    /// `false` if there is nothing to show.
+    ///
+    /// Like the detail screen, it borrows `visible_lines` and stashes the
+    /// diff view's scroll and fold state; the caller loads the diff.
+    pub fn open_timeline(&mut self) -> bool {
+        let Some(latest) = self.timeline.latest() else {
+            return false;
+        };
+        self.timeline_selected = latest.id;
+        self.timeline_mark = None;
+        if self.diff_stash.is_none() {
+            self.diff_stash = Some((self.scroll, std::mem::take(&mut self.collapsed)));
+        }
+        self.screen = Screen::Timeline;
+        self.clear_search();
+        true
+    }
+
+    /// Leave the timeline for the diff screen.
+    ///
+    /// The caller must recompute `visible_lines` for the diff view.
+    pub fn close_timeline(&mut self) {
+        self.timeline_diff.clear();
+        if let Some((scroll, collapsed)) = self.diff_stash.take() {
+            self.scroll = scroll;
+            self.collapsed = collapsed;
+        }
+        self.screen = Screen::Diff;
+        self.clear_search();
+    }
+
+    /// Select the next older or newer snapshot. Returns `false` at either
+    /// end of the timeline.
+    pub fn timeline_step(&mut self, older: bool) -> bool {
+        let id = self.timeline_selected;
+        let next = if older {
+            self.timeline.before(id)
+        } else {
+            self.timeline.after(id)
+        };
+        match next {
+            Some(snapshot) => {
+                self.timeline_selected = snapshot.id;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark the selected snapshot to compare others with, or unmark it.
+    pub fn toggle_timeline_mark(&mut self) {
+        self.timeline_mark = match self.timeline_mark {
+            Some(id) if id == self.timeline_selected => None,
+            _ => Some(self.timeline_selected),
+        };
+    }
+
+    /// Snapshot selected on the timeline screen.
+    pub fn selected_snapshot(&self) -> Option<&Snapshot> {
+        // The selection may have been dropped from the ring since
+        self.timeline
+            .get(self.timeline_selected)
+            .or_else(|| self.timeline.iter().next())
+    }
+
+    /// Snapshots the timeline diff compares, older first. `None` as the
+    /// older one stands for the HEAD the newer one was taken on.
+    pub fn timeline_compared(&self) -> Option<(Option<&Snapshot>, &Snapshot)> {
+        let selected = self.selected_snapshot()?;
+        let other = match self.timeline_mark.and_then(|id| self.timeline.get(id)) {
+            Some(mark) if mark.id != selected.id => mark,
+            _ => return Some((self.timeline.before(selected.id), selected)),
+        };
+        Some(if other.id < selected.id {
+            (Some(other), selected)
+        } else {
+            (Some(selected), other)
+        })
+    }
+
+    /// Show `files` as the timeline diff.
+    pub fn set_timeline_diff(&mut self, files: Vec<FileDiff>) {
+        self.collapsed.clear();
+        self.scroll = 0;
+        self.recompute_visible_lines(&files);
+        self.timeline_diff = files;
+        self.clear_search();
+    }
+
+    // ── Commit composer ─────────────────────────────────────────
+
+    /// Open the commit composer, keeping any unfinished draft.
+    pub fn open_commit_composer(&mut self, message_file: PathBuf) {
+        if self.commit_draft.is_none() {
+            self.commit_draft = Some(CommitDraft {
+                options: CommitOptions::default(),
+                message: String::new(),
+                message_file,
+            });
+        }
+        self.screen = Screen::Commit;
+        self.clear_search();
+    }
+
+    /// Store the edited message, dropping comment lines as `git commit` would.
+    pub fn set_commit_message(&mut self, text: &str) {
+        if let Some(draft) = self.commit_draft.as_mut() {
+            draft.message = text
+                .lines()
+                .filter(|l| !l.starts_with('#'))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim()
+                .to_string();
+        }
+    }
+
+    // ── Blame ───────────────────────────────────────────────────
+
+    /// Push a new blame view and switch to the blame screen.
+    pub fn push_blame(&mut self, path: String, rev: Option<String>, lines: Vec<BlameLine>) {
+        self.blame_stack.push(BlameView {
+            path,
+            rev,
+            lines,
+            selected: 0,
+        });
+        self.screen = Screen::Blame;
+        self.clear_search();
+    }
+
+    /// Return to the previous blame view. Returns `false` at the outermost one.
+    pub fn pop_blame(&mut self) -> bool {
+        if self.blame_stack.len() > 1 {
+            self.blame_stack.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Leave the blame screen entirely.
+    pub fn close_blame(&mut self) {
+        self.blame_stack.clear();
+        self.screen = Screen::Diff;
+    }
+
+    /// Move the blame cursor by `delta` lines, clamped to the file.
+    pub fn blame_move(&mut self, delta: isize) {
+        if let Some(view) = self.blame_stack.last_mut() {
+            let max = view.lines.len().saturating_sub(1);
+            view.selected = view.selected.saturating_add_signed(delta).min(max);
+        }
+    }
+
+    /// The blame line under the cursor.
+    pub fn blame_selected(&self) -> Option<&BlameLine> {
+        let view = self.blame_stack.last()?;
+        view.lines.get(view.selected)
+    }
+
     // ── Search ──────────────────────────────────────────────────
 
+    /// Open the search prompt. Scope, regex mode and the views searched
+    /// carry over from the previous search.
     pub fn enter_search(&mut self, forward: bool) {
         self.input_mode = InputMode::Search;
+        self.search_history.reset();
         self.search = SearchState {
-            query: String::new(),
             forward,
-            active: false,
-            matches: Vec::new(),
-            current_match: 0,
+            scope: self.search.scope,
+            literal: self.search.literal,
+            all_views: self.search.all_views,
+            origin: (self.view, self.scroll),
+            log_origin: self.commit_log_selected,
+            ..SearchState::default()
         };
     }
 
-    pub fn search_push(&mut self, c: char) {
+    pub fn search_push(&mut self, c: char, sources: &[SearchSource]) {
         self.search.query.push(c);
+        self.search_history.reset();
+        self.search_update(sources);
     }
 
-    pub fn search_pop(&mut self) {
+    pub fn search_pop(&mut self, sources: &[SearchSource]) {
         self.search.query.pop();
+        self.search_history.reset();
+        self.search_update(sources);
+    }
+
+    /// Cycle the search scope from the prompt.
+    pub fn search_cycle_scope(&mut self, sources: &[SearchSource]) {
+        self.search.scope = self.search.scope.next();
+        self.search_update(sources);
+    }
+
+    /// Replace the query with an older (`older`) or newer history entry.
+    pub fn search_browse(&mut self, older: bool, sources: &[SearchSource]) {
+        let entry = if older {
+            self.search_history.older(&self.search.query)
+        } else {
+            self.search_history.newer()
+        };
+        if let Some(entry) = entry {
+            self.search.query = entry.to_string();
+            self.search_update(sources);
+        }
+    }
+
+    /// Switch between regex and literal matching from the prompt.
+    pub fn search_toggle_literal(&mut self, sources: &[SearchSource]) {
+        self.search.literal = !self.search.literal;
+        self.search_update(sources);
+    }
+
+    /// Switch between searching the current view and both views, going
+    /// back to the view the prompt opened in. `sources` are those for the
+    /// old setting; the caller re-runs the search with the new ones.
+    pub fn search_toggle_all_views(&mut self, sources: &[SearchSource]) {
+        self.show_view(self.search.origin.0, sources);
+        self.search.all_views = !self.search.all_views;
+    }
+
+    /// Highlight matches while typing and jump to the nearest one from
+    /// where the prompt was opened.
+    pub fn search_update(&mut self, sources: &[SearchSource]) {
+        self.recompute_matches(sources);
+        if self.screen == Screen::CommitLog {
+            self.commit_log_selected = self.search.log_origin;
+            self.select_log_match(self.search.forward, true);
+            return;
+        }
+        let (view, scroll) = self.search.origin;
+        self.show_view(view, sources);
+        self.scroll = scroll;
+        if self.search.active {
+            self.search.current_match = self.nearest_match();
+            self.jump_to_current_match(sources);
+        }
+    }
+
+    /// Leave the prompt without searching, restoring the view and scroll
+    /// position.
+    pub fn search_cancel(&mut self, sources: &[SearchSource]) {
+        if self.screen == Screen::CommitLog {
+            self.commit_log_selected = self.search.log_origin;
+            self.clear_search();
+            return;
+        }
+        let (view, scroll) = self.search.origin;
+        self.show_view(view, sources);
+        self.scroll = scroll;
+        self.clear_search();
     }
 
     /// Confirm the search query and switch back to normal mode.
-    pub fn search_confirm(&mut self, lines: &[DiffLine]) {
+    pub fn search_confirm(&mut self, sources: &[SearchSource]) {
         self.input_mode = InputMode::Normal;
-        self.recompute_matches(lines);
-        if !self.search.matches.is_empty() {
-            self.search.active = true;
-            self.search.current_match = if self.search.forward {
-                self.first_match_from(self.scroll as usize)
-            } else {
-                self.last_match_before(self.scroll as usize + self.viewport_height as usize)
-            };
-            self.jump_to_current_match();
-        }
+        self.search_history.push(&self.search.query);
+        self.search_update(sources);
     }
 
-    pub fn search_next(&mut self) {
+    pub fn search_next(&mut self, sources: &[SearchSource]) {
+        if self.screen == Screen::CommitLog {
+            self.select_log_match(true, false);
+            return;
+        }
         if !self.search.active || self.search.matches.is_empty() {
             return;
         }
         self.search.current_match = (self.search.current_match + 1) % self.search.matches.len();
-        self.jump_to_current_match();
+        self.jump_to_current_match(sources);
     }
 
-    pub fn search_prev(&mut self) {
+    pub fn search_prev(&mut self, sources: &[SearchSource]) {
+        if self.screen == Screen::CommitLog {
+            self.select_log_match(false, false);
+            return;
+        }
         if !self.search.active || self.search.matches.is_empty() {
             return;
         }
@@ -297,56 +1495,240 @@ impl App {
         } else {
             self.search.current_match - 1
         };
-        self.jump_to_current_match();
+        self.jump_to_current_match(sources);
     }
 
+    /// Drop the query and matches, keeping scope, regex mode and views.
     pub fn clear_search(&mut self) {
         self.input_mode = InputMode::Normal;
-        self.search = SearchState::default();
+        self.search = SearchState {
+            scope: self.search.scope,
+            literal: self.search.literal,
+            all_views: self.search.all_views,
+            ..SearchState::default()
+        };
     }
 
-    /// Recompute all search matches for the given lines (case-insensitive).
-    pub fn recompute_matches(&mut self, lines: &[DiffLine]) {
+    /// Recompute all search matches over every file in `sources`, folded
+    /// or not, or over the commits in the log. The current match is kept
+    /// if it still exists.
+    pub fn recompute_matches(&mut self, sources: &[SearchSource]) {
+        let current = self.search.matches.get(self.search.current_match).cloned();
         self.search.matches.clear();
-        if self.search.query.is_empty() {
-            self.search.active = false;
+        self.search.log_matches.clear();
+        self.search.error = None;
+        let matcher = match Matcher::new(&self.search.query, self.search.literal, self.search.scope)
+        {
+            Ok(Some(m)) => m,
+            Ok(None) => {
+                self.search.active = false;
+                return;
+            }
+            Err(e) => {
+                // Show the last line only; the regex crate adds a caret diagram
+                self.search.error = e.to_string().lines().last().map(str::to_string);
+                self.search.active = false;
+                return;
+            }
+        };
+        if self.screen == Screen::CommitLog {
+            self.search.log_matches = self
+                .commit_log
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| {
+                    matcher.is_match(&e.message)
+                        || matcher.is_match(&e.author)
+                        || matcher.is_match(&e.hash)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            self.search.active = !self.search.log_matches.is_empty();
             return;
         }
-        let query_lower = self.search.query.to_lowercase();
-        for (line_idx, dl) in lines.iter().enumerate() {
-            let text = dl.text();
-            let lower = text.to_lowercase();
-            let mut start = 0;
-            while let Some(pos) = lower[start..].find(&query_lower) {
-                let byte_start = start + pos;
-                let byte_end = byte_start + query_lower.len();
-                self.search.matches.push((line_idx, byte_start, byte_end));
-                start = byte_end;
+        for &(view, files) in sources {
+            // Empty filenames come from RepoState::empty and have no header
+            for fd in files.iter().filter(|fd| !fd.filename.is_empty()) {
+                let header = DiffLine::FileHeader {
+                    filename: fd.filename.clone(),
+                    added: fd.added,
+                    removed: fd.removed,
+                };
+                let lines = std::iter::once((None, &header))
+                    .chain(fd.lines.iter().enumerate().map(|(i, dl)| (Some(i), dl)));
+                for (line, dl) in lines {
+                    for (start, end) in matcher.find(dl) {
+                        self.search.matches.push(SearchMatch {
+                            view,
+                            file: fd.filename.clone(),
+                            line,
+                            start,
+                            end,
+                        });
+                    }
+                }
             }
         }
-        self.search.active = !self.search.matches.is_empty();
+        let len = self.search.matches.len();
+        self.search.current_match = current
+            .and_then(|c| self.search.matches.iter().position(|m| *m == c))
+            .unwrap_or(self.search.current_match)
+            .min(len.saturating_sub(1));
+        self.search.active = len > 0;
     }
 
-    fn jump_to_current_match(&mut self) {
-        if let Some(&(line_idx, _, _)) = self.search.matches.get(self.search.current_match) {
-            let target = (line_idx as u16).saturating_sub(5);
-            self.scroll = target.min(self.max_scroll());
+    /// Select the first matching commit after (`forward`) or before the
+    /// selected one, or the selected one itself if `inclusive`, wrapping
+    /// around.
+    fn select_log_match(&mut self, forward: bool, inclusive: bool) {
+        let selected = self.commit_log_selected;
+        let matches = &self.search.log_matches;
+        let found = if forward {
+            matches
+                .iter()
+                .find(|&&i| i > selected || (inclusive && i == selected))
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&i| i < selected || (inclusive && i == selected))
+                .or(matches.last())
+        };
+        if let Some(&i) = found {
+            self.commit_log_selected = i;
         }
     }
 
-    fn first_match_from(&self, line: usize) -> usize {
-        self.search
-            .matches
+    /// Number of matches on this screen: commits in the log, matches in
+    /// the diff elsewhere.
+    pub fn search_match_count(&self) -> usize {
+        match self.screen {
+            Screen::CommitLog => self.search.log_matches.len(),
+            _ => self.search.matches.len(),
+        }
+    }
+
+    /// Number of the current match, from 1, and the match count. `None`
+    /// without matches, or in the log when the selected commit is not one.
+    pub fn search_position(&self) -> Option<(usize, usize)> {
+        if !self.search.active {
+            return None;
+        }
+        let current = match self.screen {
+            Screen::CommitLog => self
+                .search
+                .log_matches
+                .iter()
+                .position(|&i| i == self.commit_log_selected)?,
+            _ if self.search.matches.is_empty() => return None,
+            _ => self.search.current_match,
+        };
+        Some((current + 1, self.search_match_count()))
+    }
+
+    /// Matches shown in `visible_lines`, keyed by line, as
+    /// `(byte_start, byte_end, is_current)`.
+    pub fn visible_matches(&self) -> HashMap<usize, Vec<(usize, usize, bool)>> {
+        let mut spots: HashMap<usize, Vec<_>> = HashMap::new();
+        if !self.search.active {
+            return spots;
+        }
+        let headers = self.header_lines();
+        for (i, m) in self.search.matches.iter().enumerate() {
+            if m.line.is_some() && self.collapsed.contains(&m.file) {
+                continue;
+            }
+            if let Some(pos) = self.match_anchor(m, &headers) {
+                spots.entry(pos).or_default().push((
+                    m.start,
+                    m.end,
+                    i == self.search.current_match,
+                ));
+            }
+        }
+        spots
+    }
+
+    /// Line of each file header in `visible_lines`.
+    fn header_lines(&self) -> HashMap<&str, usize> {
+        self.file_header_positions
             .iter()
-            .position(|(li, _, _)| *li >= line)
-            .unwrap_or(0)
+            .filter_map(|&pos| match &self.visible_lines[pos] {
+                DiffLine::FileHeader { filename, .. } => Some((filename.as_str(), pos)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Where `m` is in `visible_lines`: its own line, or its file's header
+    /// while the file is folded. `None` for matches in the other view.
+    fn match_anchor(&self, m: &SearchMatch, headers: &HashMap<&str, usize>) -> Option<usize> {
+        if m.view != self.view {
+            return None;
+        }
+        let header = *headers.get(m.file.as_str())?;
+        match m.line {
+            Some(line) if !self.collapsed.contains(&m.file) => Some(header + 1 + line),
+            _ => Some(header),
+        }
+    }
+
+    /// Switch to `view` without resetting the search, rebuilding
+    /// `visible_lines` from its entry in `sources`.
+    fn show_view(&mut self, view: DiffView, sources: &[SearchSource]) {
+        if view == self.view {
+            return;
+        }
+        if let Some((_, files)) = sources.iter().find(|(v, _)| *v == view) {
+            self.view = view;
+            self.recompute_visible_lines(files);
+        }
     }
 
-    fn last_match_before(&self, line: usize) -> usize {
-        self.search
+    /// For `/` the first match of the current view at or after the scroll
+    /// position, for `?` the last one on screen or above. Falls back to
+    /// the first or last match anywhere.
+    fn nearest_match(&self) -> usize {
+        let headers = self.header_lines();
+        let anchors = self
+            .search
             .matches
             .iter()
-            .rposition(|(li, _, _)| *li <= line)
-            .unwrap_or(self.search.matches.len().saturating_sub(1))
+            .enumerate()
+            .filter_map(|(i, m)| Some((i, self.match_anchor(m, &headers)?)));
+        let top = self.scroll as usize;
+        if self.search.forward {
+            anchors
+                .filter(|&(_, pos)| pos >= top)
+                .map(|(i, _)| i)
+                .next()
+                .unwrap_or(0)
+        } else {
+            let bottom = top + self.viewport_height as usize;
+            anchors
+                .filter(|&(_, pos)| pos <= bottom)
+                .map(|(i, _)| i)
+                .next_back()
+                .unwrap_or(self.search.matches.len().saturating_sub(1))
+        }
+    }
+
+    /// Scroll to the current match, switching views and unfolding its
+    /// file as needed.
+    fn jump_to_current_match(&mut self, sources: &[SearchSource]) {
+        let Some(m) = self.search.matches.get(self.search.current_match).cloned() else {
+            return;
+        };
+        self.show_view(m.view, sources);
+        if m.line.is_some() && self.collapsed.remove(&m.file) {
+            if let Some((_, files)) = sources.iter().find(|(v, _)| *v == m.view) {
+                self.recompute_visible_lines(files);
+            }
+        }
+        if let Some(pos) = self.match_anchor(&m, &self.header_lines()) {
+            let target = (pos as u16).saturating_sub(5);
+            self.scroll = target.min(self.max_scroll());
+        }
     }
 }
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::app::DiffView;
+use crate::diff::{DiffLine, FileDiff};
+
+/// Largest LCS table built when aligning a file's old and new diff; past
+/// this, only the common prefix and suffix keep their marks.
+const MAX_ALIGN_CELLS: usize = 1 << 20;
+
+/// When each diff line and file last changed, for highlighting what a
+/// refresh brought in.
+///
+/// Marks follow their lines as the diff shifts: each refresh aligns the
+/// new diff of a file with the old one and carries marks across.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeMarks {
+    files: HashMap<(DiffView, String), FileMarks>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct FileMarks {
+    /// When the file's diff last changed.
+    touched: Option<Instant>,
+    /// One entry per line of the file's diff.
+    lines: Vec<Option<Instant>>,
+}
+
+impl ChangeMarks {
+    /// Move the marks of `view` from the `old` diffs to `new`. With `now`,
+    /// added and removed lines that weren't in `old` are marked with it;
+    /// without, marks are only carried over. Marks older than `ttl` are
+    /// dropped.
+    pub fn update(
+        &mut self,
+        view: DiffView,
+        old: &[FileDiff],
+        new: &[FileDiff],
+        now: Option<Instant>,
+        ttl: Duration,
+    ) {
+        let fresh = |t: &Option<Instant>| t.filter(|t| t.elapsed() < ttl);
+        let mut files = HashMap::new();
+        for fd in new.iter().filter(|fd| !fd.filename.is_empty()) {
+            let key = (view, fd.filename.clone());
+            let prev = self.files.remove(&key).unwrap_or_default();
+            let old_lines = old
+                .iter()
+                .find(|o| o.filename == fd.filename)
+                .map_or(&[][..], |o| &o.lines[..]);
+
+            let mut changed = false;
+            let lines: Vec<Option<Instant>> = align(old_lines, &fd.lines)
+                .into_iter()
+                .zip(&fd.lines)
+                .map(|(from, dl)| match from {
+                    Some(j) => prev.lines.get(j).and_then(fresh),
+                    None if matches!(dl, DiffLine::Added(_) | DiffLine::Removed(_)) => {
+                        changed |= now.is_some();
+                        now
+                    }
+                    None => None,
+                })
+                .collect();
+            let touched = if changed { now } else { fresh(&prev.touched) };
+            if touched.is_some() || lines.iter().any(Option::is_some) {
+                files.insert(key, FileMarks { touched, lines });
+            }
+        }
+        // Keep the other view's marks
+        files.extend(self.files.drain().filter(|((v, _), _)| *v != view));
+        self.files = files;
+    }
+
+    /// When line `line` of `file`'s diff changed, if it's marked.
+    pub fn line(&self, view: DiffView, file: &str, line: usize) -> Option<Instant> {
+        self.get(view, file)?.lines.get(line).copied().flatten()
+    }
+
+    /// When `file`'s diff last changed, if it's marked.
+    pub fn touched(&self, view: DiffView, file: &str) -> Option<Instant> {
+        self.get(view, file)?.touched
+    }
+
+    /// File and line of the newest marked line in `view`.
+    pub fn latest(&self, view: DiffView) -> Option<(&str, usize)> {
+        self.newest(view, |_| true)
+            .map(|(_, file, line)| (file, line))
+    }
+
+    /// Line of the newest change to `file` in `view`, if it was marked at
+    /// or after `since`.
+    pub fn latest_since(&self, view: DiffView, file: &str, since: Instant) -> Option<usize> {
+        self.newest(view, |f| f == file)
+            .filter(|(t, _, _)| *t >= since)
+            .map(|(_, _, line)| line)
+    }
+
+    /// Time, file and line of the newest marked line in `view` among the
+    /// files `keep` accepts.
+    fn newest(
+        &self,
+        view: DiffView,
+        keep: impl Fn(&str) -> bool,
+    ) -> Option<(Instant, &str, usize)> {
+        self.files
+            .iter()
+            .filter(|((v, file), _)| *v == view && keep(file))
+            .flat_map(|((_, file), marks)| {
+                marks
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, t)| Some((*t.as_ref()?, file.as_str(), i)))
+            })
+            // The first line of the newest change
+            .max_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)))
+    }
+
+    /// Drop marks older than `ttl`.
+    pub fn expire(&mut self, ttl: Duration) {
+        let fresh = |t: &mut Option<Instant>| {
+            if t.is_some_and(|t| t.elapsed() >= ttl) {
+                *t = None;
+            }
+            t.is_some()
+        };
+        self.files.retain(|_, marks| {
+            let touched = fresh(&mut marks.touched);
+            let mut any_line = false;
+            for line in &mut marks.lines {
+                any_line |= fresh(line);
+            }
+            touched || any_line
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    fn get(&self, view: DiffView, file: &str) -> Option<&FileMarks> {
+        // Avoids allocating a key per lookup while rendering
+        self.files
+            .iter()
+            .find(|((v, f), _)| *v == view && f == file)
+            .map(|(_, marks)| marks)
+    }
+}
+
+/// For each line of `new`, the index of the same line in `old`, found as
+/// a longest common subsequence.
+fn align(old: &[DiffLine], new: &[DiffLine]) -> Vec<Option<usize>> {
+    let same = |a: &DiffLine, b: &DiffLine| a.text() == b.text();
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+
+    let mut map: Vec<Option<usize>> = vec![None; new.len()];
+    for (i, slot) in map.iter_mut().enumerate().take(prefix) {
+        *slot = Some(i);
+    }
+    for k in 0..suffix {
+        map[new.len() - 1 - k] = Some(old.len() - 1 - k);
+    }
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if a.is_empty() || b.is_empty() || a.len() * b.len() > MAX_ALIGN_CELLS {
+        return map;
+    }
+    // lcs[i][j]: LCS length of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if same(&a[i], &b[j]) {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if same(&a[i], &b[j]) {
+            map[prefix + j] = Some(prefix + i);
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::parse_files;
+
+    fn diff(body: &[&str]) -> Vec<FileDiff> {
+        let mut raw = String::from("diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -1 +1 @@\n");
+        for line in body {
+            raw.push_str(line);
+            raw.push('\n');
+        }
+        parse_files(&raw)
+    }
+
+    fn marked(marks: &ChangeMarks, files: &[FileDiff]) -> Vec<usize> {
+        (0..files[0].lines.len())
+            .filter(|&i| marks.line(DiffView::Unstaged, "f", i).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn marks_new_lines_and_carries_them() {
+        let ttl = Duration::from_secs(60);
+        let mut marks = ChangeMarks::default();
+        let v1 = diff(&[" a", "+b"]);
+        let v2 = diff(&[" a", "+new", "+b"]);
+        let v3 = diff(&["+top", " a", "+new", "+b"]);
+
+        let start = Instant::now();
+        marks.update(DiffView::Unstaged, &v1, &v2, Some(start), ttl);
+        // Header lines 0-3, then " a" at 4
+        assert_eq!(marked(&marks, &v2), [5]);
+        assert!(marks.touched(DiffView::Unstaged, "f").is_some());
+
+        // Carrying only: the mark moves down with its line
+        marks.update(DiffView::Unstaged, &v2, &v3, None, ttl);
+        assert_eq!(marked(&marks, &v3), [6]);
+        assert_eq!(marks.latest(DiffView::Unstaged), Some(("f", 6)));
+        assert_eq!(marks.latest_since(DiffView::Unstaged, "f", start), Some(6));
+        assert_eq!(marks.latest_since(DiffView::Unstaged, "g", start), None);
+    }
+
+    #[test]
+    fn expired_marks_are_dropped() {
+        let mut marks = ChangeMarks::default();
+        let v1 = diff(&["+a"]);
+        let v2 = diff(&["+a", "+b"]);
+        marks.update(
+            DiffView::Unstaged,
+            &v1,
+            &v2,
+            Some(Instant::now()),
+            Duration::ZERO,
+        );
+        marks.update(DiffView::Unstaged, &v2, &v2, None, Duration::ZERO);
+        assert!(marks.is_empty());
+    }
+}
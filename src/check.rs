@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::event::AppEvent;
+use crate::pathspec::PathFilter;
+use crate::process::{self, Pid};
+
+/// The `[check]` table: a command run after every change.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckConfig {
+    /// Label for the status bar and the panel; defaults to `check`.
+    pub name: Option<String>,
+    /// Shell command run from the repository root, e.g. `cargo test`.
+    pub command: String,
+    /// Only run for changes to these paths or globs.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+/// How one check run ended.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Which run this was; results of cancelled runs are dropped.
+    pub run: u64,
+    /// Exit code; `None` if the command was killed or could not start.
+    pub code: Option<i32>,
+    pub success: bool,
+    /// Standard output and error, interleaved.
+    pub output: String,
+    pub duration: Duration,
+}
+
+/// A `file:line` reference found in the check output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRef {
+    /// Path relative to the repository root.
+    pub path: String,
+    pub line: usize,
+    /// Line of the output it appears on.
+    pub output_line: usize,
+}
+
+/// The configured check, the run in progress and the latest result.
+#[derive(Debug, Default)]
+pub struct Check {
+    config: Option<CheckConfig>,
+    filter: PathFilter,
+    /// Id of the latest run started.
+    run: u64,
+    /// Process id and start time of the run in progress.
+    running: Option<(Pid, Instant)>,
+    pub last: Option<CheckResult>,
+    /// References in the output of `last`.
+    pub refs: Vec<FileRef>,
+    /// Index into `refs` of the reference jumped to last.
+    pub current_ref: Option<usize>,
+}
+
+impl Check {
+    pub fn new(config: Option<&CheckConfig>) -> Self {
+        Self {
+            // Validated when the config was loaded
+            filter: config
+                .and_then(|c| PathFilter::new(c.paths.clone()).ok())
+                .unwrap_or_default(),
+            config: config.cloned(),
+            ..Self::default()
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    pub fn name(&self) -> &str {
+        self.config
+            .as_ref()
+            .and_then(|c| c.name.as_deref())
+            .unwrap_or("check")
+    }
+
+    /// Start time of the run in progress.
+    pub fn started(&self) -> Option<Instant> {
+        self.running.as_ref().map(|(_, started)| *started)
+    }
+
+    /// Whether saving `changed` calls for a new run.
+    pub fn wants(&self, changed: &[PathBuf]) -> bool {
+        self.enabled() && changed.iter().any(|p| self.filter.matches(p))
+    }
+
+    /// Start a run in the background, cancelling the one in progress. The
+    /// result arrives as `AppEvent::CheckDone`.
+    pub fn start(&mut self, repo: &Path, tx: &Sender<AppEvent>) -> Result<()> {
+        let Some(command) = self.config.as_ref().map(|c| c.command.clone()) else {
+            return Ok(());
+        };
+        self.cancel();
+        self.run += 1;
+        let pid = spawn(self.run, &command, repo, tx.clone())?;
+        self.running = Some((pid, Instant::now()));
+        Ok(())
+    }
+
+    /// Stop the run in progress, along with everything it started.
+    pub fn cancel(&mut self) {
+        if let Some((pid, _)) = self.running.take() {
+            process::kill(&pid);
+        }
+    }
+
+    /// Record the result of the latest run and the references in its
+    /// output. Results of cancelled runs are dropped.
+    pub fn finish(&mut self, result: CheckResult, repo: &Path) {
+        if result.run != self.run {
+            return;
+        }
+        self.running = None;
+        self.refs = parse_refs(&result.output, repo, |path| repo.join(path).is_file());
+        self.current_ref = None;
+        self.last = Some(result);
+    }
+
+    /// Move to the next reference, wrapping around.
+    pub fn next_ref(&mut self) -> Option<&FileRef> {
+        if self.refs.is_empty() {
+            return None;
+        }
+        let next = self.current_ref.map_or(0, |i| (i + 1) % self.refs.len());
+        self.current_ref = Some(next);
+        self.refs.get(next)
+    }
+}
+
+/// Run `command` through `sh` in a process group of its own. Returns the
+/// process id, cleared once the run ends.
+fn spawn(run: u64, command: &str, repo: &Path, tx: Sender<AppEvent>) -> Result<Pid> {
+    process::spawn(process::shell(command, repo), None, move |exit| {
+        let _ = tx.send(AppEvent::CheckDone(CheckResult {
+            run,
+            code: exit.code,
+            success: exit.success,
+            output: exit.output,
+            duration: exit.duration,
+        }));
+    })
+}
+
+/// `path:line` with an optional `:column`, as printed by compilers, test
+/// runners and linters.
+static FILE_REF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[\s(\[<'`=])(?:\./)?([\w./-]*[\w-]\.\w+):(\d+)").expect("valid regex")
+});
+
+/// `file:line` references in `output`, first occurrence only. Absolute
+/// paths inside `repo` are made relative; paths `exists` rejects are left
+/// out.
+fn parse_refs(output: &str, repo: &Path, exists: impl Fn(&str) -> bool) -> Vec<FileRef> {
+    let mut refs: Vec<FileRef> = Vec::new();
+    for (output_line, text) in output.lines().enumerate() {
+        for caps in FILE_REF.captures_iter(text) {
+            let path = Path::new(&caps[1]);
+            let path = path.strip_prefix(repo).unwrap_or(path).to_string_lossy();
+            let Ok(line) = caps[2].parse() else {
+                continue;
+            };
+            if refs.iter().any(|r| r.path == path && r.line == line) || !exists(&path) {
+                continue;
+            }
+            refs.push(FileRef {
+                path: path.into_owned(),
+                line,
+                output_line,
+            });
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_from_compiler_and_test_output() {
+        let output = "\
+error[E0308]: mismatched types
+  --> src/app.rs:42:9
+thread 'tests::x' panicked at ./src/diff.rs:7:5:
+see https://example.com:8080 and /repo/src/app.rs:42
+  at Object.<anonymous> (/repo/web/index.test.ts:13:1)
+";
+        let refs = parse_refs(output, Path::new("/repo"), |p| p != "example.com");
+        let found: Vec<(&str, usize, usize)> = refs
+            .iter()
+            .map(|r| (r.path.as_str(), r.line, r.output_line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("src/app.rs", 42, 1),
+                ("src/diff.rs", 7, 2),
+                ("web/index.test.ts", 13, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn process_id_is_cleared_when_the_run_ends() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let pid = spawn(1, "exit 3", Path::new("."), tx).unwrap();
+        let Ok(AppEvent::CheckDone(result)) = rx.recv() else {
+            panic!("no check result");
+        };
+        assert_eq!(result.code, Some(3));
+        assert!(pid.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::git::LogFilter;
+use crate::patch::ExportScope;
+use crate::pathspec::PathFilter;
+
+/// A line typed into the `:` prompt.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// `:base [REF]` — diff against REF instead of HEAD/the index; no ref
+    /// goes back to the usual diffs.
+    Base(Option<String>),
+    /// `:filter [PATHS]` — same as the `P` prompt.
+    Filter(PathFilter),
+    /// `:log [FILTERS]` — open the commit log with `LogFilter` syntax.
+    Log(LogFilter),
+    /// `:w [SCOPE] FILE` — write part of the current diff as a patch,
+    /// relative to the repository root. No scope means the visual
+    /// selection, or else the whole view. An existing file is only
+    /// replaced by `:w!`, flagged by the `bool`.
+    Write(Option<ExportScope>, PathBuf, bool),
+    /// `:yank [SCOPE]` — copy part of the current diff as a patch to the
+    /// clipboard, with the same default as `:w`.
+    Yank(Option<ExportScope>),
+    /// `:q`
+    Quit,
+}
+
+/// Command names, for completion. Short forms are accepted too.
+const COMMANDS: [&str; 6] = ["base", "filter", "log", "quit", "write", "yank"];
+
+/// `log` filter keys, for completion.
+const LOG_KEYS: [&str; 6] = ["all", "author=", "branch=", "path=", "since=", "until="];
+
+impl Command {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+        let args = args.trim();
+        match name {
+            "base" if args.starts_with('-') => bail!("invalid revision `{args}`"),
+            "base" => Ok(Command::Base((!args.is_empty()).then(|| args.to_string()))),
+            "filter" => Ok(Command::Filter(PathFilter::parse(args)?)),
+            "log" => Ok(Command::Log(LogFilter::parse(args)?)),
+            "w" | "write" | "w!" | "write!" => {
+                // A leading scope word only counts when a file name follows
+                let (scope, path) = match args.split_once(' ') {
+                    Some((word, rest)) => match ExportScope::parse(word) {
+                        Some(scope) => (Some(scope), rest.trim()),
+                        None => (None, args),
+                    },
+                    None => (None, args),
+                };
+                if path.is_empty() {
+                    bail!(":{name} needs a file name");
+                }
+                let force = name.ends_with('!');
+                Ok(Command::Write(scope, PathBuf::from(path), force))
+            }
+            "y" | "yank" if args.is_empty() => Ok(Command::Yank(None)),
+            "y" | "yank" => match ExportScope::parse(args) {
+                Some(scope) => Ok(Command::Yank(Some(scope))),
+                None => bail!("unknown scope `{args}` ({})", ExportScope::NAMES.join(", ")),
+            },
+            "q" | "quit" => Ok(Command::Quit),
+            "" => bail!("empty command"),
+            _ => bail!("unknown command `{name}` ({})", COMMANDS.join(", ")),
+        }
+    }
+}
+
+/// What the word before the cursor completes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Command,
+    Ref,
+    Path,
+    LogKey,
+    Scope,
+    ScopeOrPath,
+}
+
+/// Completions for the last word of `input`: the byte offset the word
+/// starts at and the candidates replacing it, sorted. Refs come from
+/// `refs`, paths are listed relative to `root`.
+pub fn complete(input: &str, refs: &[String], root: &Path) -> (usize, Vec<String>) {
+    let start = input.rfind(' ').map_or(0, |i| i + 1);
+    let word = &input[start..];
+    let kind = match input.split_once(' ').map(|(name, _)| name) {
+        None => Kind::Command,
+        Some("base") => Kind::Ref,
+        Some("filter") => Kind::Path,
+        // The first argument may be a scope instead of the file
+        Some("w" | "write" | "w!" | "write!") if !input[..start].trim_end().contains(' ') => {
+            Kind::ScopeOrPath
+        }
+        Some("w" | "write" | "w!" | "write!") => Kind::Path,
+        Some("y" | "yank") => Kind::Scope,
+        Some("log") => match word.split_once('=') {
+            Some(("branch", _)) => Kind::Ref,
+            Some(("path", _)) => Kind::Path,
+            Some(_) => return (start, Vec::new()),
+            None => Kind::LogKey,
+        },
+        Some(_) => return (start, Vec::new()),
+    };
+    // Values of `key=` complete after the `=`
+    let (start, word) = match word.split_once('=') {
+        Some((key, value)) => (start + key.len() + 1, value),
+        None => (start, word),
+    };
+    let mut candidates: Vec<String> = match kind {
+        Kind::Command => COMMANDS.iter().map(|c| c.to_string()).collect(),
+        Kind::LogKey => LOG_KEYS.iter().map(|k| k.to_string()).collect(),
+        Kind::Scope => ExportScope::NAMES.iter().map(|s| s.to_string()).collect(),
+        Kind::ScopeOrPath => ExportScope::NAMES
+            .iter()
+            .map(|s| s.to_string())
+            .chain(list_paths(root, word))
+            .collect(),
+        Kind::Ref => refs.to_vec(),
+        Kind::Path => list_paths(root, word),
+    };
+    candidates.retain(|c| c.starts_with(word));
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+/// Entries of the directory part of `word`, directories with a trailing
+/// slash. Dotfiles only show up once the name starts with a dot.
+fn list_paths(root: &Path, word: &str) -> Vec<String> {
+    let dir = match word.rfind('/') {
+        Some(i) => &word[..=i],
+        None => "",
+    };
+    let Ok(entries) = fs::read_dir(root.join(dir)) else {
+        return Vec::new();
+    };
+    let show_hidden = word[dir.len()..].starts_with('.');
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            if name == ".git" || (name.starts_with('.') && !show_hidden) {
+                return None;
+            }
+            let slash = if e.file_type().ok()?.is_dir() {
+                "/"
+            } else {
+                ""
+            };
+            Some(format!("{dir}{name}{slash}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert!(matches!(Command::parse("base main"), Ok(Command::Base(Some(r))) if r == "main"));
+        assert!(matches!(Command::parse("base"), Ok(Command::Base(None))));
+        assert!(
+            matches!(Command::parse("filter src/ *.rs"), Ok(Command::Filter(f)) if f.specs().len() == 2)
+        );
+        assert!(
+            matches!(Command::parse("log author=alice"), Ok(Command::Log(f)) if f.author.as_deref() == Some("alice"))
+        );
+        assert!(
+            matches!(Command::parse("w out.patch"), Ok(Command::Write(None, p, false)) if p == Path::new("out.patch"))
+        );
+        assert!(matches!(
+            Command::parse("w hunk out.patch"),
+            Ok(Command::Write(Some(ExportScope::Hunk), p, false)) if p == Path::new("out.patch")
+        ));
+        assert!(matches!(
+            Command::parse("write! out.patch"),
+            Ok(Command::Write(None, _, true))
+        ));
+        assert!(Command::parse("w").is_err());
+        assert!(Command::parse("w!").is_err());
+        assert!(matches!(
+            Command::parse("yank file"),
+            Ok(Command::Yank(Some(ExportScope::File)))
+        ));
+        assert!(Command::parse("yank lines").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn complete_commands_refs_and_keys() {
+        let refs = vec!["main".to_string(), "origin/main".into(), "feature".into()];
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(complete("fi", &refs, root), (0, vec!["filter".into()]));
+        assert_eq!(complete("base ma", &refs, root), (5, vec!["main".into()]));
+        assert_eq!(
+            complete("log all branch=o", &refs, root),
+            (15, vec!["origin/main".into()])
+        );
+        assert_eq!(complete("log au", &refs, root), (4, vec!["author=".into()]));
+    }
+
+    #[test]
+    fn complete_paths() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let (start, candidates) = complete("filter src/comm", &[], root);
+        assert_eq!(start, 7);
+        assert_eq!(candidates, ["src/command.rs"]);
+        let (_, candidates) = complete("w sr", &[], root);
+        assert_eq!(candidates, ["src/"]);
+        let (_, candidates) = complete("w hunk sr", &[], root);
+        assert_eq!(candidates, ["src/"]);
+        let (_, candidates) = complete("w h", &[], root);
+        assert_eq!(candidates, ["hunk"]);
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::app::DiffView;
+use crate::check::CheckConfig;
+use crate::hooks::HookConfig;
+use crate::keymap::{KeyMap, KeyOverrides};
+use crate::pathspec::PathFilter;
+use crate::theme::{self, Theme};
+
+/// A `[themes.<name>]` table: style overrides plus an optional `base`.
+type ThemeTable = BTreeMap<String, toml::Value>;
+
+/// File name of the per-repo config, looked up in the repository root.
+const REPO_CONFIG: &str = ".git-monitor.toml";
+
+/// How the filesystem watcher detects changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherMode {
+    /// OS notifications (inotify, FSEvents, ReadDirectoryChangesW).
+    Native,
+    /// Periodic scanning — for network filesystems and containers where
+    /// native notifications don't arrive.
+    Poll,
+}
+
+/// One config file as written by the user. Every field is optional so
+/// files can be layered: global, then per-repo, then CLI flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub debounce_ms: Option<u64>,
+    pub default_view: Option<DiffView>,
+    pub log_page_size: Option<usize>,
+    pub pager: Option<String>,
+    pub watcher: Option<WatcherMode>,
+    pub poll_interval_ms: Option<u64>,
+    /// Built-in theme name or a key of `themes`.
+    pub theme: Option<String>,
+    pub themes: BTreeMap<String, ThemeTable>,
+    pub mouse: Option<bool>,
+    pub sidebar: Option<bool>,
+    /// How long lines changed by a refresh stay highlighted; 0 disables.
+    pub highlight_changes_secs: Option<u64>,
+    /// Working tree snapshots kept for the timeline; 0 disables it.
+    pub timeline_size: Option<usize>,
+    /// Restrict the live diff to these paths or globs.
+    pub paths: Option<Vec<String>>,
+    /// `[keys.<screen>]` tables of `action = ["key", ...]`.
+    pub keys: KeyOverrides,
+    /// `[[hooks]]` commands run when the repository changes.
+    pub hooks: Option<Vec<HookConfig>>,
+    /// `[check]` command run after every change.
+    pub check: Option<CheckConfig>,
+}
+
+/// Fully resolved settings used by the rest of the app.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub debounce_ms: u64,
+    pub default_view: DiffView,
+    pub log_page_size: usize,
+    /// Pager command; `None` means auto-detect like git does.
+    pub pager: Option<String>,
+    pub watcher: WatcherMode,
+    pub poll_interval_ms: u64,
+    pub theme_name: String,
+    /// User-defined themes from every config layer.
+    pub user_themes: BTreeMap<String, ThemeTable>,
+    /// Resolved from `theme_name` once all layers are merged.
+    pub theme: Theme,
+    /// Capture mouse events at startup.
+    pub mouse: bool,
+    /// Show the file tree sidebar at startup.
+    pub sidebar: bool,
+    /// How long lines changed by a refresh stay highlighted; 0 disables.
+    pub highlight_changes_secs: u64,
+    /// Working tree snapshots kept for the timeline; 0 disables it.
+    pub timeline_size: usize,
+    /// Pathspecs the live diff is restricted to; empty for the whole repo.
+    pub paths: Vec<String>,
+    /// Key overrides from every config layer, later layers winning per action.
+    pub key_overrides: KeyOverrides,
+    /// Default bindings with `key_overrides` applied.
+    pub keymap: KeyMap,
+    /// Commands run when the repository changes, from the global config only.
+    pub hooks: Vec<HookConfig>,
+    /// Command run after every change, from the global config only.
+    pub check: Option<CheckConfig>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 200,
+            default_view: DiffView::Unstaged,
+            log_page_size: 50,
+            pager: None,
+            watcher: WatcherMode::Native,
+            poll_interval_ms: 1000,
+            theme_name: "dark".to_string(),
+            user_themes: BTreeMap::new(),
+            theme: Theme::dark(),
+            mouse: true,
+            sidebar: false,
+            highlight_changes_secs: 10,
+            timeline_size: 0,
+            paths: Vec::new(),
+            key_overrides: KeyOverrides::new(),
+            keymap: KeyMap::default(),
+            hooks: Vec::new(),
+            check: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Apply the fields set in `file` on top of the current settings.
+    pub fn merge(&mut self, file: ConfigFile) {
+        if let Some(v) = file.debounce_ms {
+            self.debounce_ms = v;
+        }
+        if let Some(v) = file.default_view {
+            self.default_view = v;
+        }
+        if let Some(v) = file.log_page_size {
+            self.log_page_size = v;
+        }
+        if let Some(v) = file.pager {
+            self.pager = Some(v);
+        }
+        if let Some(v) = file.watcher {
+            self.watcher = v;
+        }
+        if let Some(v) = file.poll_interval_ms {
+            self.poll_interval_ms = v;
+        }
+        if let Some(v) = file.theme {
+            self.theme_name = v;
+        }
+        self.user_themes.extend(file.themes);
+        if let Some(v) = file.mouse {
+            self.mouse = v;
+        }
+        if let Some(v) = file.sidebar {
+            self.sidebar = v;
+        }
+        if let Some(v) = file.highlight_changes_secs {
+            self.highlight_changes_secs = v;
+        }
+        if let Some(v) = file.timeline_size {
+            self.timeline_size = v;
+        }
+        if let Some(v) = file.paths {
+            self.paths = v;
+        }
+        if let Some(v) = file.hooks {
+            self.hooks = v;
+        }
+        if let Some(v) = file.check {
+            self.check = Some(v);
+        }
+        for (screen, actions) in file.keys {
+            self.key_overrides
+                .entry(screen)
+                .or_default()
+                .extend(actions);
+        }
+        // Each layer was validated on its own, so the merge is valid too
+        if let Ok(keymap) = KeyMap::with_overrides(&self.key_overrides) {
+            self.keymap = keymap;
+        }
+    }
+
+    /// Resolve `theme_name` against user and built-in themes.
+    ///
+    /// `NO_COLOR` always wins and selects the monochrome theme.
+    pub fn resolve_theme(&mut self) -> Result<()> {
+        if theme::no_color() {
+            self.theme = Theme::mono();
+            return Ok(());
+        }
+        self.theme = build_theme(&self.theme_name, &self.user_themes)?;
+        Ok(())
+    }
+}
+
+/// Build a named theme: a built-in, or a user theme applied to its `base`.
+fn build_theme(name: &str, user_themes: &BTreeMap<String, ThemeTable>) -> Result<Theme> {
+    if let Some(table) = user_themes.get(name) {
+        let base = match table.get("base") {
+            Some(v) => v
+                .as_str()
+                .with_context(|| format!("theme `{name}`: base must be a string"))?,
+            None => "dark",
+        };
+        let mut theme = Theme::builtin(base).with_context(|| {
+            format!(
+                "theme `{name}`: unknown base `{base}` (built-in: {})",
+                theme::BUILTIN.join(", ")
+            )
+        })?;
+        theme
+            .apply(table)
+            .with_context(|| format!("theme `{name}`"))?;
+        return Ok(theme);
+    }
+    Theme::builtin(name).with_context(|| {
+        format!(
+            "unknown theme `{name}` (built-in: {})",
+            theme::BUILTIN.join(", ")
+        )
+    })
+}
+
+/// Default global config path: `$XDG_CONFIG_HOME/git-monitor/config.toml`,
+/// falling back to `~/.config/git-monitor/config.toml`.
+pub fn global_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("git-monitor").join("config.toml"))
+}
+
+/// Load settings from the global config (or `explicit`, if given) and the
+/// repo's `.git-monitor.toml`.
+///
+/// A file that fails to parse or validate is skipped and its error is
+/// returned alongside the settings, so the app can still start and report
+/// the problem. The theme is left unresolved so CLI flags can still pick
+/// one — call `Settings::resolve_theme` afterwards.
+pub fn load(repo: &Path, explicit: Option<&Path>) -> (Settings, Vec<String>) {
+    let mut settings = Settings::default();
+    let mut errors = Vec::new();
+
+    let global = explicit.map(Path::to_path_buf).or_else(global_config_path);
+    let repo_config = repo.join(REPO_CONFIG);
+    let layers = [global, Some(repo_config.clone())];
+
+    for path in layers.into_iter().flatten() {
+        // An explicit --config must exist; the default locations are optional
+        if !path.exists() && Some(path.as_path()) != explicit {
+            continue;
+        }
+        match read_config(&path) {
+            Ok(mut file) => {
+                if path == repo_config {
+                    let stripped = strip_commands(&mut file);
+                    if !stripped.is_empty() {
+                        errors.push(format!(
+                            "{}: {} only read from the global config",
+                            path.display(),
+                            stripped.join(", ")
+                        ));
+                    }
+                }
+                settings.merge(file);
+            }
+            Err(e) => errors.push(format!("{}: {e:#}", path.display())),
+        }
+    }
+
+    (settings, errors)
+}
+
+/// Drop the settings that run commands from a repository's config: a
+/// cloned repository must not run commands just by being opened. Returns
+/// the names of the ones it had.
+fn strip_commands(file: &mut ConfigFile) -> Vec<&'static str> {
+    let mut stripped = Vec::new();
+    if file.pager.take().is_some() {
+        stripped.push("pager");
+    }
+    if file.hooks.take().is_some() {
+        stripped.push("hooks");
+    }
+    if file.check.take().is_some() {
+        stripped.push("check");
+    }
+    stripped
+}
+
+fn read_config(path: &Path) -> Result<ConfigFile> {
+    let text = std::fs::read_to_string(path).context("cannot read file")?;
+    let file = parse(&text)?;
+    Ok(file)
+}
+
+/// Parse and validate one config file.
+fn parse(text: &str) -> Result<ConfigFile> {
+    let file: ConfigFile = toml::from_str(text)?;
+    if let Some(ms) = file.debounce_ms {
+        if !(10..=10_000).contains(&ms) {
+            bail!("debounce_ms must be between 10 and 10000, got {ms}");
+        }
+    }
+    if let Some(n) = file.log_page_size {
+        if !(1..=10_000).contains(&n) {
+            bail!("log_page_size must be between 1 and 10000, got {n}");
+        }
+    }
+    if let Some(ms) = file.poll_interval_ms {
+        if ms < 100 {
+            bail!("poll_interval_ms must be at least 100, got {ms}");
+        }
+    }
+    if let Some(secs) = file.highlight_changes_secs {
+        if secs > 3600 {
+            bail!("highlight_changes_secs must be at most 3600, got {secs}");
+        }
+    }
+    if let Some(size) = file.timeline_size {
+        if size > 10_000 {
+            bail!("timeline_size must be at most 10000, got {size}");
+        }
+    }
+    if file.pager.as_deref().is_some_and(|p| p.trim().is_empty()) {
+        bail!("pager must not be empty");
+    }
+    // Check user themes here so errors point at the file that defines them
+    for name in file.themes.keys() {
+        build_theme(name, &file.themes)?;
+    }
+    KeyMap::with_overrides(&file.keys)?;
+    if let Some(paths) = &file.paths {
+        PathFilter::new(paths.clone()).context("paths")?;
+    }
+    for hook in file.hooks.iter().flatten() {
+        if hook.command.trim().is_empty() {
+            bail!("hook commands must not be empty");
+        }
+        if hook.timeout_secs == Some(0) {
+            bail!("hook `{}`: timeout_secs must be at least 1", hook.name());
+        }
+        PathFilter::new(hook.paths.clone()).with_context(|| format!("hook `{}`", hook.name()))?;
+    }
+    if let Some(check) = &file.check {
+        if check.command.trim().is_empty() {
+            bail!("check.command must not be empty");
+        }
+        PathFilter::new(check.paths.clone()).context("check")?;
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    use crate::app::Screen;
+    use crate::hooks::Trigger;
+    use crate::keymap::Action;
+
+    #[test]
+    fn parse_full_config() {
+        let file = parse(
+            r#"
+            debounce_ms = 500
+            default_view = "staged"
+            log_page_size = 100
+            pager = "delta"
+            watcher = "poll"
+            "#,
+        )
+        .unwrap();
+        let mut settings = Settings::default();
+        settings.merge(file);
+        assert_eq!(settings.debounce_ms, 500);
+        assert_eq!(settings.default_view, DiffView::Staged);
+        assert_eq!(settings.log_page_size, 100);
+        assert_eq!(settings.pager.as_deref(), Some("delta"));
+        assert_eq!(settings.watcher, WatcherMode::Poll);
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let mut settings = Settings::default();
+        settings.merge(parse("debounce_ms = 500\nlog_page_size = 20").unwrap());
+        settings.merge(parse("debounce_ms = 50").unwrap());
+        assert_eq!(settings.debounce_ms, 50);
+        assert_eq!(settings.log_page_size, 20);
+    }
+
+    #[test]
+    fn user_theme_resolves_against_base() {
+        let mut settings = Settings::default();
+        settings.merge(
+            parse(
+                r##"
+                theme = "mine"
+                [themes.mine]
+                base = "light"
+                added = "#00ff00"
+                lanes = ["red", "blue"]
+                "##,
+            )
+            .unwrap(),
+        );
+        let theme = build_theme(&settings.theme_name, &settings.user_themes).unwrap();
+        assert_eq!(theme.added.fg, Some(Color::Rgb(0, 255, 0)));
+        assert_eq!(theme.lanes, [Color::Red, Color::Blue]);
+        assert_eq!(theme.hunk, Theme::light().hunk);
+    }
+
+    #[test]
+    fn bad_themes_are_rejected() {
+        assert!(parse("[themes.x]\nbase = \"neon\"").is_err());
+        assert!(parse("[themes.x]\nadded = \"chartreuse\"").is_err());
+        assert!(build_theme("nope", &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn key_overrides_merge_per_action() {
+        let mut settings = Settings::default();
+        settings.merge(parse("[keys.diff]\nscroll_down = [\"e\"]\nquit = [\"Q\"]").unwrap());
+        settings.merge(parse("[keys.diff]\nquit = [\"x\"]").unwrap());
+        let keymap = &settings.keymap;
+        assert_eq!(keymap.keys_for(Screen::Diff, Action::ScrollDown), ["e"]);
+        assert_eq!(keymap.keys_for(Screen::Diff, Action::Quit), ["x"]);
+        assert!(parse("[keys.diff]\nwarp = [\"w\"]").is_err());
+    }
+
+    #[test]
+    fn hooks_are_validated() {
+        let file = parse("[[hooks]]\ncommand = \"make\"\non = \"staged\"").unwrap();
+        assert_eq!(file.hooks.unwrap()[0].on, Trigger::Staged);
+        assert!(parse("[[hooks]]\ncommand = \" \"").is_err());
+        assert!(parse("[[hooks]]\ncommand = \"make\"\non = \"push\"").is_err());
+        assert!(parse("[[hooks]]\ncommand = \"make\"\npaths = [\":(glob)x\"]").is_err());
+        assert!(parse("[[hooks]]\ncommand = \"make\"\ntimeout_secs = 0").is_err());
+    }
+
+    #[test]
+    fn paths_are_validated() {
+        assert_eq!(
+            parse("paths = [\"src/\"]").unwrap().paths.unwrap(),
+            ["src/"]
+        );
+        let err = parse("paths = [\":(glob)x\"]").unwrap_err();
+        assert!(format!("{err:#}").starts_with("paths: "));
+    }
+
+    #[test]
+    fn repo_config_cannot_run_commands() {
+        let mut file = parse("pager = \"sh evil.sh\"\n[check]\ncommand = \"make\"").unwrap();
+        assert_eq!(strip_commands(&mut file), ["pager", "check"]);
+        assert!(file.pager.is_none() && file.check.is_none());
+        assert!(strip_commands(&mut file).is_empty());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("debounce = 10").is_err());
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        assert!(parse("debounce_ms = 0").is_err());
+        assert!(parse("log_page_size = 0").is_err());
+        assert!(parse("default_view = \"sideways\"").is_err());
+    }
+}
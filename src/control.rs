@@ -0,0 +1,271 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+use crate::app::DiffView;
+use crate::command::Command;
+use crate::event::AppEvent;
+
+/// A line sent to the control socket.
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// `view staged|unstaged` — show that diff.
+    View(DiffView),
+    /// `base [REF]` and `filter [PATHS]`, as typed at the `:` prompt.
+    Command(Command),
+    /// `jump FILE[:LINE]` — scroll to line LINE of FILE, or to its header
+    /// without a line. FILE may be absolute.
+    Jump(String, Option<usize>),
+    /// `refresh` — query git again.
+    Refresh,
+    /// `state [hunks]` — the `--json` report of the last refresh.
+    State { hunks: bool },
+}
+
+/// Request names, for error messages.
+const REQUESTS: [&str; 6] = ["base", "filter", "jump", "refresh", "state", "view"];
+
+impl Request {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+        let args = args.trim();
+        match name {
+            "view" => match args {
+                "staged" => Ok(Request::View(DiffView::Staged)),
+                "unstaged" => Ok(Request::View(DiffView::Unstaged)),
+                _ => bail!("unknown view `{args}` (staged, unstaged)"),
+            },
+            "base" | "filter" => Ok(Request::Command(Command::parse(input)?)),
+            "jump" => {
+                // Only a number after the last colon is a line
+                let (path, line) = match args.rsplit_once(':') {
+                    Some((path, line)) if line.bytes().all(|b| b.is_ascii_digit()) => {
+                        let line = line
+                            .parse()
+                            .with_context(|| format!("invalid line `{line}`"))?;
+                        (path, Some(line))
+                    }
+                    _ => (args, None),
+                };
+                if path.is_empty() {
+                    bail!("jump needs a file");
+                }
+                Ok(Request::Jump(path.to_string(), line))
+            }
+            "refresh" if args.is_empty() => Ok(Request::Refresh),
+            "state" if args.is_empty() => Ok(Request::State { hunks: false }),
+            "state" if args == "hunks" => Ok(Request::State { hunks: true }),
+            "refresh" | "state" => bail!("unexpected `{args}` after {name}"),
+            "" => bail!("empty request"),
+            _ => bail!("unknown request `{name}` ({})", REQUESTS.join(", ")),
+        }
+    }
+}
+
+/// Answer to a request: the report for `state`, nothing for the others,
+/// or what went wrong.
+pub type Reply = Result<Option<Value>, String>;
+
+/// `reply` as the line written back to the client.
+fn encode(reply: Reply) -> String {
+    match reply {
+        Ok(None) => json!({ "ok": true }),
+        Ok(Some(state)) => json!({ "ok": true, "state": state }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    }
+    .to_string()
+}
+
+/// `path` relative to `repo`, for absolute paths inside it.
+pub fn relative(path: &str, repo: &Path) -> String {
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        return path.to_string_lossy().into_owned();
+    }
+    // The repository path is canonical, the editor's may not be
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    path.strip_prefix(repo)
+        .or_else(|_| canonical.strip_prefix(repo))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Keeps the socket open — dropping it stops listening and removes the
+/// socket file.
+pub struct ControlHandle {
+    path: PathBuf,
+}
+
+impl Drop for ControlHandle {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Listen on a Unix domain socket at `path`, readable by the current user
+/// only. Every line a client writes is parsed into a `Request` and sent as
+/// `AppEvent::Control`; the reply goes back as one line of JSON.
+#[cfg(unix)]
+pub fn listen(path: &Path, tx: Sender<AppEvent>) -> Result<ControlHandle> {
+    use std::fs;
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::thread;
+
+    if let Ok(meta) = fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(path).is_ok() {
+            bail!("another git-monitor is listening on {}", path.display());
+        }
+        // Left behind by a monitor that did not exit cleanly
+        fs::remove_file(path)?;
+    }
+    // Bind in a directory only we can enter and move the socket into place
+    // once its mode is set, so no one else can connect in between
+    let Some(name) = path.file_name() else {
+        bail!("{} is not a file path", path.display());
+    };
+    let dir = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("cannot create {}", dir.display()))?;
+    let private = dir.join("socket");
+    let bound = UnixListener::bind(&private).and_then(|listener| {
+        fs::set_permissions(&private, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&private, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private);
+    let _ = fs::remove_dir(&dir);
+    let listener = bound.with_context(|| format!("cannot listen on {}", path.display()))?;
+    let handle = ControlHandle {
+        path: path.to_path_buf(),
+    };
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let tx = tx.clone();
+            thread::spawn(move || serve(stream, tx));
+        }
+    });
+    Ok(handle)
+}
+
+#[cfg(not(unix))]
+pub fn listen(_path: &Path, _tx: Sender<AppEvent>) -> Result<ControlHandle> {
+    bail!("the control socket needs Unix domain sockets")
+}
+
+/// Answer the requests of one client, in order, until it disconnects or
+/// the main loop goes away.
+#[cfg(unix)]
+fn serve(stream: std::os::unix::net::UnixStream, tx: Sender<AppEvent>) {
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::mpsc;
+
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match Request::parse(&line) {
+            Ok(request) => {
+                let (reply_tx, reply_rx) = mpsc::channel();
+                if tx.send(AppEvent::Control(request, reply_tx)).is_err() {
+                    break;
+                }
+                match reply_rx.recv() {
+                    Ok(reply) => reply,
+                    Err(_) => break,
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        if writeln!(writer, "{}", encode(reply)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_requests() {
+        assert!(matches!(
+            Request::parse("view staged"),
+            Ok(Request::View(DiffView::Staged))
+        ));
+        assert!(Request::parse("view both").is_err());
+        assert!(matches!(
+            Request::parse("base main"),
+            Ok(Request::Command(Command::Base(Some(r)))) if r == "main"
+        ));
+        assert!(matches!(
+            Request::parse("jump src/app.rs:42"),
+            Ok(Request::Jump(p, Some(42))) if p == "src/app.rs"
+        ));
+        // Colons in file names, and no line
+        assert!(matches!(
+            Request::parse("jump notes:v2.md"),
+            Ok(Request::Jump(p, None)) if p == "notes:v2.md"
+        ));
+        assert!(Request::parse("jump").is_err());
+        assert!(matches!(
+            Request::parse("state hunks"),
+            Ok(Request::State { hunks: true })
+        ));
+        assert!(Request::parse("refresh now").is_err());
+        assert!(Request::parse("quit").is_err());
+    }
+
+    #[test]
+    fn replies_are_json_lines() {
+        assert_eq!(encode(Ok(None)), r#"{"ok":true}"#);
+        assert_eq!(
+            encode(Err("no such file".into())),
+            r#"{"error":"no such file","ok":false}"#
+        );
+        assert_eq!(relative("/repo/src/a.rs", Path::new("/repo")), "src/a.rs");
+        assert_eq!(relative("src/a.rs", Path::new("/repo")), "src/a.rs");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("git-monitor-control-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sock");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let handle = listen(&path, tx).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // Only the socket is left behind while listening
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        drop(handle);
+        assert!(!path.exists());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -63,6 +63,41 @@ pub fn parse_files(raw: &str) -> Vec<FileDiff> {
     files
 }
 
+/// Index into `fd.lines` of the line showing line `line` of the new file.
+/// If no hunk covers it, the header of the hunk with the closest line.
+pub fn new_line_index(fd: &FileDiff, line: usize) -> Option<usize> {
+    // Line number of the next new-file line, once inside a hunk
+    let mut next: Option<usize> = None;
+    let mut hunk = 0;
+    let mut closest: Option<(usize, usize)> = None;
+    for (i, dl) in fd.lines.iter().enumerate() {
+        match dl {
+            DiffLine::Hunk(header) => {
+                next = hunk_new_start(header);
+                hunk = i;
+            }
+            DiffLine::Added(_) | DiffLine::Context(_) if !dl.text().starts_with('\\') => {
+                let Some(n) = next else { continue };
+                if n == line {
+                    return Some(i);
+                }
+                if closest.is_none_or(|(distance, _)| n.abs_diff(line) < distance) {
+                    closest = Some((n.abs_diff(line), hunk));
+                }
+                next = Some(n + 1);
+            }
+            _ => {}
+        }
+    }
+    closest.map(|(_, hunk)| hunk)
+}
+
+/// New-file start line of a `@@ -a,b +c,d @@` header.
+fn hunk_new_start(header: &str) -> Option<usize> {
+    let (_, new) = header.split_once(" +")?;
+    new.split([',', ' ']).next()?.parse().ok()
+}
+
 /// Build a `FileDiff` from a slice of raw lines belonging to one file.
 fn build_file_diff(raw_lines: &[&str]) -> FileDiff {
     let filename = extract_filename(raw_lines[0]);
@@ -0,0 +1,51 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Detect the user's preferred editor, the way `git commit` does.
+/// Checks GIT_EDITOR -> git config core.editor -> VISUAL -> EDITOR -> "vi"
+pub fn detect_editor(repo: &Path) -> String {
+    if let Ok(editor) = std::env::var("GIT_EDITOR") {
+        if !editor.is_empty() {
+            return editor;
+        }
+    }
+
+    if let Ok(output) = Command::new("git")
+        .args(["-C", &repo.to_string_lossy(), "config", "core.editor"])
+        .output()
+    {
+        if output.status.success() {
+            let editor = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !editor.is_empty() {
+                return editor;
+            }
+        }
+    }
+
+    for var in ["VISUAL", "EDITOR"] {
+        if let Ok(editor) = std::env::var(var) {
+            if !editor.is_empty() {
+                return editor;
+            }
+        }
+    }
+
+    "vi".to_string()
+}
+
+/// Open `path` in the editor and wait for it to exit.
+///
+/// The command goes through `sh -c` so editor strings with arguments
+/// (`code --wait`, `emacs -nw`) work as they do for git.
+pub fn open_editor(path: &Path, editor_cmd: &str) -> std::io::Result<()> {
+    let status = Command::new("sh")
+        .args(["-c", &format!("{editor_cmd} \"$1\""), "editor"])
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!(
+            "editor exited with {status}"
+        )));
+    }
+    Ok(())
+}
@@ -1,11 +1,89 @@
-use crossterm::event::KeyEvent;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender};
+
+use crossterm::event::{KeyEvent, MouseEvent};
+
+use crate::check::CheckResult;
+use crate::control::{Reply, Request};
+use crate::hooks::HookResult;
 
 /// All events funnelled through the main loop's mpsc channel.
 pub enum AppEvent {
     /// A keypress from the keyboard-reading thread.
     Key(KeyEvent),
-    /// The filesystem watcher detected a change (debounced).
-    FsChange,
+    /// A mouse event, only delivered while mouse capture is on.
+    Mouse(MouseEvent),
+    /// The filesystem watcher detected a change (debounced). Carries the
+    /// working tree files that changed, relative to the repository root
+    /// and most recently modified first; empty when only `.git/` changed.
+    FsChange(Vec<PathBuf>),
+    /// A hook command finished running.
+    HookDone(HookResult),
+    /// A check run finished, possibly one that was cancelled since.
+    CheckDone(CheckResult),
+    /// A request from the control socket, with where to send the reply.
+    Control(Request, Sender<Reply>),
     /// The terminal was resized — triggers a re-render.
     Resize,
 }
+
+/// Empty the queue of keyboard, mouse and resize events, which were meant
+/// for a program that had the terminal, and return the other events in
+/// order, to be handled once the main loop takes over again.
+pub fn drain_input(rx: &Receiver<AppEvent>) -> Vec<AppEvent> {
+    rx.try_iter()
+        .filter(|event| {
+            !matches!(
+                event,
+                AppEvent::Key(_) | AppEvent::Mouse(_) | AppEvent::Resize
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::hooks::{HookConfig, Hooks, Trigger};
+
+    #[test]
+    fn hooks_finishing_while_suspended_are_kept() {
+        let (tx, rx) = mpsc::channel();
+        let result = HookResult {
+            hook: 0,
+            code: Some(0),
+            success: true,
+            output: String::new(),
+            duration: Duration::ZERO,
+            finished: Instant::now(),
+            timed_out: None,
+        };
+        tx.send(AppEvent::Key(KeyCode::Char('q').into())).unwrap();
+        tx.send(AppEvent::HookDone(result)).unwrap();
+        tx.send(AppEvent::Resize).unwrap();
+
+        let kept = drain_input(&rx);
+        assert_eq!(kept.len(), 1);
+        assert!(rx.try_recv().is_err());
+
+        let mut hooks = Hooks::new(&[HookConfig {
+            name: None,
+            command: "true".into(),
+            paths: Vec::new(),
+            on: Trigger::Change,
+            timeout_secs: None,
+        }]);
+        for event in kept {
+            if let AppEvent::HookDone(result) = event {
+                hooks.finish(result, Path::new("."), &tx);
+            }
+        }
+        assert!(hooks.latest().is_some_and(|(_, result)| result.success));
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
 
@@ -10,9 +10,249 @@ use crate::diff::{self, FileDiff};
 #[derive(Debug, Clone)]
 pub struct CommitEntry {
     pub hash: String,
+    pub full_hash: String,
     pub message: String,
     pub author: String,
     pub date_relative: String,
+    pub date_absolute: String,
+    /// Ref decorations, e.g. `HEAD -> main, origin/main, tag: v1.0`.
+    pub refs: String,
+    /// Full hashes of the parent commits (empty for a root commit).
+    pub parents: Vec<String>,
+}
+
+/// One line of `git blame` output.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub hash: String,
+    pub author: String,
+    /// Author timestamp (seconds since the epoch).
+    pub author_time: i64,
+    pub summary: String,
+    /// Line number in the blamed revision of the file.
+    pub line_no: usize,
+    pub content: String,
+    /// Parent commit and the file's path there, for re-blaming past this change.
+    pub previous: Option<(String, String)>,
+}
+
+impl BlameLine {
+    /// Whether the line only exists in the working tree.
+    pub fn is_uncommitted(&self) -> bool {
+        self.hash.bytes().all(|b| b == b'0')
+    }
+}
+
+/// Flags for `git commit` chosen in the commit composer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommitOptions {
+    pub amend: bool,
+    pub signoff: bool,
+    pub no_verify: bool,
+}
+
+/// Server-side history search, mapped onto `git log` options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogSearch {
+    /// `--grep`: commit message matches (case-insensitive).
+    Message(String),
+    /// `--author`: author name or email matches.
+    Author(String),
+    /// `-S`: commits that add or remove the given string.
+    Pickaxe(String),
+}
+
+impl LogSearch {
+    /// Parse a history search prompt.
+    ///
+    /// `author:alice` and `code:fn_name` select `--author` and `-S`;
+    /// anything else searches commit messages. Empty values are no search.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let search = if let Some(rest) = input.strip_prefix("author:") {
+            LogSearch::Author(rest.trim().to_string())
+        } else if let Some(rest) = input.strip_prefix("code:") {
+            LogSearch::Pickaxe(rest.trim().to_string())
+        } else {
+            LogSearch::Message(input.to_string())
+        };
+        match &search {
+            LogSearch::Message(s) | LogSearch::Author(s) | LogSearch::Pickaxe(s)
+                if s.is_empty() =>
+            {
+                None
+            }
+            _ => Some(search),
+        }
+    }
+
+    /// Short human-readable form for titles, e.g. `grep: fix`.
+    pub fn label(&self) -> String {
+        match self {
+            LogSearch::Message(s) => format!("grep: {s}"),
+            LogSearch::Author(s) => format!("author: {s}"),
+            LogSearch::Pickaxe(s) => format!("code: {s}"),
+        }
+    }
+}
+
+/// Which commits the log covers, set from the log filter prompt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Only commits touching this path (`-- <path>`).
+    pub path: Option<String>,
+    pub author: Option<String>,
+    /// Passed to `--since`, e.g. `2.weeks` or `2024-01-01`.
+    pub since: Option<String>,
+    /// Passed to `--until`.
+    pub until: Option<String>,
+    /// Start the walk from this ref instead of HEAD.
+    pub branch: Option<String>,
+    /// Walk every ref (`--all`); overrides `branch`.
+    pub all: bool,
+}
+
+impl LogFilter {
+    /// Parse space-separated `key=value` tokens, e.g.
+    /// `author=alice since=2.weeks path=src/ all`. Values with spaces are
+    /// quoted: `author="Jane Doe"`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut filter = LogFilter::default();
+        for token in split_words(input)? {
+            if token == "all" {
+                filter.all = true;
+                continue;
+            }
+            let Some((key, value)) = token.split_once('=') else {
+                bail!("expected key=value, got `{token}`");
+            };
+            // The branch is passed to git as a revision, not an option
+            if key == "branch" && value.starts_with('-') {
+                bail!("invalid branch `{value}`");
+            }
+            let value = Some(value.to_string());
+            match key {
+                "path" => filter.path = value,
+                "author" => filter.author = value,
+                "since" => filter.since = value,
+                "until" => filter.until = value,
+                "branch" => filter.branch = value,
+                _ => bail!("unknown filter `{key}`"),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == LogFilter::default()
+    }
+}
+
+impl std::fmt::Display for LogFilter {
+    /// Formats back into the syntax accepted by `LogFilter::parse`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = Vec::new();
+        for (key, value) in [
+            ("path", &self.path),
+            ("author", &self.author),
+            ("since", &self.since),
+            ("until", &self.until),
+            ("branch", &self.branch),
+        ] {
+            if let Some(v) = value {
+                tokens.push(format!("{key}={}", quote_word(v)));
+            }
+        }
+        if self.all {
+            tokens.push("all".to_string());
+        }
+        write!(f, "{}", tokens.join(" "))
+    }
+}
+
+/// Split `input` on whitespace, keeping text in single or double quotes
+/// together and dropping the quotes.
+fn split_words(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in input.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        bail!("unterminated {q}");
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// `value` quoted for `split_words` if it needs to be.
+fn quote_word(value: &str) -> String {
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        value.to_string()
+    } else if value.contains('"') {
+        format!("'{value}'")
+    } else {
+        format!("\"{value}\"")
+    }
+}
+
+/// Parameters for one page of `git log`.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Number of commits to skip (already loaded pages).
+    pub skip: usize,
+    /// Page size.
+    pub count: usize,
+    pub search: Option<LogSearch>,
+    pub filter: LogFilter,
+    /// Use `--topo-order` so the commit graph stays readable.
+    pub topo_order: bool,
+}
+
+impl LogQuery {
+    /// Whether commits in the middle of the history are left out without
+    /// git rewriting the parents of the others past them. Path filters
+    /// are rewritten; author filters and history searches are not.
+    pub fn skips_parents(&self) -> bool {
+        self.filter.author.is_some() || self.search.is_some()
+    }
+}
+
+/// Full metadata and per-file diff for a single commit.
+#[derive(Debug, Clone)]
+pub struct CommitDetail {
+    pub hash: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    pub author_email: String,
+    pub author_date: String,
+    pub committer: String,
+    pub committer_email: String,
+    pub committer_date: String,
+    /// Full commit message (subject + body), trailers stripped.
+    pub message: String,
+    /// `Key: value` trailers such as `Signed-off-by`.
+    pub trailers: Vec<(String, String)>,
+    pub files: Vec<FileDiff>,
+}
+
+/// One path from `git status`, with its index and work-tree status codes
+/// (`M`, `A`, `D`, `R`, `?`, or a space for unchanged).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStatus {
+    pub path: String,
+    pub index: char,
+    pub worktree: char,
 }
 
 /// Snapshot of everything we need from git to render one frame.
@@ -24,19 +264,28 @@ pub struct RepoState {
     pub unstaged_count: usize,
     pub unstaged_diff: Vec<FileDiff>,
     pub staged_diff: Vec<FileDiff>,
+    /// Every changed or untracked path.
+    pub files: Vec<FileStatus>,
     pub refreshed_at: Instant,
 }
 
 impl RepoState {
-    /// Build a complete snapshot by shelling out to git.
+    /// Build a complete snapshot by shelling out to git, with status and
+    /// diffs restricted to `paths` (all paths if empty). With a `base`
+    /// ref, the diffs compare the working tree and the index to it.
     ///
     /// Tolerant of empty repos (no commits yet) — falls back gracefully.
-    pub fn query(repo: &Path) -> Result<Self> {
+    pub fn query(repo: &Path, paths: &[String], base: Option<&str>) -> Result<Self> {
         let branch = git_branch(repo).unwrap_or_else(|_| "(no branch)".into());
         let (hash, msg) = git_last_commit(repo).unwrap_or((None, None));
-        let (staged, unstaged) = git_status_counts(repo)?;
-        let unstaged_raw = git_diff(repo, false).unwrap_or_default();
-        let staged_raw = git_diff(repo, true).unwrap_or_default();
+        let files = git_status(repo, paths)?;
+        let staged = files
+            .iter()
+            .filter(|f| f.index != ' ' && f.index != '?')
+            .count();
+        let unstaged = files.iter().filter(|f| f.worktree != ' ').count();
+        let unstaged_raw = git_diff(repo, false, base, paths).unwrap_or_default();
+        let staged_raw = git_diff(repo, true, base, paths).unwrap_or_default();
 
         Ok(Self {
             branch,
@@ -46,6 +295,7 @@ impl RepoState {
             unstaged_count: unstaged,
             unstaged_diff: diff::parse_files(&unstaged_raw),
             staged_diff: diff::parse_files(&staged_raw),
+            files,
             refreshed_at: Instant::now(),
         })
     }
@@ -65,6 +315,7 @@ impl RepoState {
                 lines: vec![diff::DiffLine::Context(reason.to_string())],
             }],
             staged_diff: vec![],
+            files: vec![],
             refreshed_at: Instant::now(),
         }
     }
@@ -73,9 +324,18 @@ impl RepoState {
 // ── helpers ─────────────────────────────────────────────────────
 
 fn run_git(repo: &Path, args: &[&str]) -> Result<String> {
-    let output = Command::new("git")
-        .args(["-C", &repo.to_string_lossy()])
-        .args(args)
+    capture(git_command(repo, args), args)
+}
+
+fn git_command(repo: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.args(["-C", &repo.to_string_lossy()]).args(args);
+    cmd
+}
+
+/// Run `cmd` (git with `args`) and return its stdout.
+fn capture(mut cmd: Command, args: &[&str]) -> Result<String> {
+    let output = cmd
         .output()
         .with_context(|| format!("failed to run git {}", args.join(" ")))?;
 
@@ -112,58 +372,586 @@ fn git_last_commit(repo: &Path) -> Result<(Option<String>, Option<String>)> {
     Ok((hash, msg))
 }
 
-fn git_status_counts(repo: &Path) -> Result<(usize, usize)> {
-    let out = run_git(repo, &["status", "--porcelain"])?;
-    let mut staged = 0;
-    let mut unstaged = 0;
-    for line in out.lines() {
-        if line.len() < 2 {
+fn git_status(repo: &Path, paths: &[String]) -> Result<Vec<FileStatus>> {
+    let mut args = vec!["status", "--porcelain", "-z", "--untracked-files=all", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let out = run_git(repo, &args)?;
+    Ok(parse_status(&out))
+}
+
+/// Parse `git status --porcelain -z`. Renames and copies are followed by
+/// their source path, which is skipped.
+fn parse_status(out: &str) -> Vec<FileStatus> {
+    let mut files = Vec::new();
+    let mut entries = out.split('\0');
+    while let Some(entry) = entries.next() {
+        let mut chars = entry.chars();
+        let (Some(index), Some(worktree), Some(' ')) = (chars.next(), chars.next(), chars.next())
+        else {
             continue;
+        };
+        if matches!(index, 'R' | 'C') {
+            entries.next();
         }
-        let bytes = line.as_bytes();
-        // First column: index (staged) status
-        if bytes[0] != b' ' && bytes[0] != b'?' {
-            staged += 1;
-        }
-        // Second column: working-tree (unstaged) status
-        if bytes[1] != b' ' {
-            unstaged += 1;
-        }
+        files.push(FileStatus {
+            path: chars.as_str().to_string(),
+            index,
+            worktree,
+        });
     }
-    Ok((staged, unstaged))
+    files
 }
 
-fn git_diff(repo: &Path, staged: bool) -> Result<String> {
+fn git_diff(repo: &Path, staged: bool, base: Option<&str>, paths: &[String]) -> Result<String> {
     let mut args = vec!["diff"];
     if staged {
         args.push("--cached");
     }
+    args.extend(base);
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
     run_git(repo, &args)
 }
 
-/// Fetch recent commits as structured entries.
-pub fn git_log(repo: &Path, count: usize) -> Result<Vec<CommitEntry>> {
-    let count_str = format!("-{count}");
-    let out = run_git(
+/// Check that `rev` names a commit.
+pub fn git_verify_commit(repo: &Path, rev: &str) -> Result<()> {
+    run_git(
         repo,
-        &["log", "--format=%h%x00%s%x00%an%x00%ar", &count_str],
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{rev}^{{commit}}"),
+        ],
+    )
+    .with_context(|| format!("unknown revision `{rev}`"))?;
+    Ok(())
+}
+
+/// Short names of all branches, remote branches and tags.
+pub fn git_refs(repo: &Path) -> Result<Vec<String>> {
+    let raw = run_git(
+        repo,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)",
+            "refs/heads",
+            "refs/remotes",
+            "refs/tags",
+        ],
     )?;
+    Ok(raw.lines().map(String::from).collect())
+}
+
+// ── Working tree snapshots ──────────────────────────────────────
+
+/// Tree with no entries, to diff against before the first commit.
+pub const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Store the working tree, untracked files included, as a tree object and
+/// return its id. Works on a scratch copy of the index, so the staging
+/// area is left alone and unchanged files aren't hashed again.
+pub fn git_snapshot_tree(repo: &Path) -> Result<String> {
+    let index = git_path(repo, "index")?;
+    let scratch = snapshot_index(repo)?;
+    // No index yet (nothing staged ever) — start from an empty one
+    if std::fs::copy(&index, &scratch).is_err() {
+        let _ = std::fs::remove_file(&scratch);
+    }
+    let run = |args: &[&str]| {
+        let mut cmd = git_command(repo, args);
+        cmd.env("GIT_INDEX_FILE", &scratch);
+        capture(cmd, args)
+    };
+    run(&["add", "--all"])?;
+    Ok(run(&["write-tree"])?.trim().to_string())
+}
+
+/// Remove the scratch index of `git_snapshot_tree`, if there is one.
+pub fn git_remove_snapshot_index(repo: &Path) {
+    if let Ok(scratch) = snapshot_index(repo) {
+        let _ = std::fs::remove_file(scratch);
+    }
+}
+
+/// Scratch index of this process, so monitors of the same repository
+/// don't overwrite each other's.
+fn snapshot_index(repo: &Path) -> Result<PathBuf> {
+    git_path(
+        repo,
+        &format!("git-monitor-snapshot-{}.index", std::process::id()),
+    )
+}
+
+/// Full id of HEAD, if there is a commit.
+pub fn git_head(repo: &Path) -> Option<String> {
+    run_git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"])
+        .ok()
+        .map(|out| out.trim().to_string())
+}
+
+/// Diff between two trees or commits, restricted to `paths`.
+pub fn git_diff_trees(
+    repo: &Path,
+    from: &str,
+    to: &str,
+    paths: &[String],
+) -> Result<Vec<FileDiff>> {
+    let mut args = vec!["diff", from, to, "--"];
+    args.extend(paths.iter().map(String::as_str));
+    Ok(diff::parse_files(&run_git(repo, &args)?))
+}
+
+/// Files changed between two trees, with lines added and removed.
+pub fn git_numstat(repo: &Path, from: &str, to: &str) -> Result<Vec<(String, usize, usize)>> {
+    let out = run_git(repo, &["diff", "--numstat", "-z", "--no-renames", from, to])?;
+    Ok(parse_numstat(&out))
+}
+
+/// Parse `git diff --numstat -z`. Binary files count as no lines.
+fn parse_numstat(out: &str) -> Vec<(String, usize, usize)> {
+    out.split('\0')
+        .filter_map(|entry| {
+            let mut fields = entry.splitn(3, '\t');
+            let added = fields.next()?.parse().unwrap_or(0);
+            let removed = fields.next()?.parse().unwrap_or(0);
+            Some((fields.next()?.to_string(), added, removed))
+        })
+        .collect()
+}
+
+/// Fetch one page of commits as structured entries.
+pub fn git_log(repo: &Path, query: &LogQuery) -> Result<Vec<CommitEntry>> {
+    let mut args: Vec<String> = vec![
+        "log".into(),
+        "--format=%h%x00%H%x00%s%x00%an%x00%ar%x00%ad%x00%D%x00%P".into(),
+        "--date=format:%Y-%m-%d %H:%M".into(),
+        format!("--skip={}", query.skip),
+        format!("-{}", query.count),
+        // Path-limited logs list the nearest shown ancestors as parents
+        "--parents".into(),
+    ];
+    let filter = &query.filter;
+    if let Some(author) = &filter.author {
+        args.push(format!("--author={author}"));
+    }
+    if let Some(since) = &filter.since {
+        args.push(format!("--since={since}"));
+    }
+    if let Some(until) = &filter.until {
+        args.push(format!("--until={until}"));
+    }
+    if query.topo_order {
+        args.push("--topo-order".into());
+    }
+    match &query.search {
+        Some(LogSearch::Message(s)) => {
+            args.push("-i".into());
+            args.push(format!("--grep={s}"));
+        }
+        Some(LogSearch::Author(s)) => args.push(format!("--author={s}")),
+        Some(LogSearch::Pickaxe(s)) => args.push(format!("-S{s}")),
+        None => {}
+    }
+    if filter.all {
+        args.push("--all".into());
+    } else if let Some(branch) = &filter.branch {
+        args.push(branch.clone());
+    }
+    args.push("--".into());
+    if let Some(path) = &filter.path {
+        args.push(path.clone());
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let out = run_git(repo, &args)?;
+    Ok(parse_log(&out))
+}
+
+fn parse_log(out: &str) -> Vec<CommitEntry> {
     let mut entries = Vec::new();
     for line in out.lines() {
-        let parts: Vec<&str> = line.splitn(4, '\0').collect();
-        if parts.len() == 4 {
+        let parts: Vec<&str> = line.splitn(8, '\0').collect();
+        if parts.len() == 8 {
             entries.push(CommitEntry {
                 hash: parts[0].to_string(),
-                message: parts[1].to_string(),
-                author: parts[2].to_string(),
-                date_relative: parts[3].to_string(),
+                full_hash: parts[1].to_string(),
+                message: parts[2].to_string(),
+                author: parts[3].to_string(),
+                date_relative: parts[4].to_string(),
+                date_absolute: parts[5].to_string(),
+                refs: parts[6].to_string(),
+                parents: parts[7].split_whitespace().map(String::from).collect(),
             });
         }
     }
-    Ok(entries)
+    entries
 }
 
 /// Get the full output of `git show <hash>` for piping to an external pager.
 pub fn git_show(repo: &Path, hash: &str) -> Result<String> {
     run_git(repo, &["show", hash])
 }
+
+/// Resolve a path inside the git directory, e.g. `COMMIT_EDITMSG`.
+///
+/// Goes through `rev-parse --git-path` so worktrees and `.git` files work.
+pub fn git_path(repo: &Path, name: &str) -> Result<PathBuf> {
+    let out = run_git(repo, &["rev-parse", "--git-path", name])?;
+    Ok(repo.join(out.trim()))
+}
+
+/// Full message of `rev`, used to pre-fill an amend.
+pub fn git_commit_message(repo: &Path, rev: &str) -> Result<String> {
+    run_git(repo, &["log", "-1", "--format=%B", rev])
+}
+
+/// Commit the index with the message in `message_file`.
+///
+/// Comment lines are stripped as in an interactive commit. On failure the
+/// error carries both stdout and stderr so hook output isn't lost.
+pub fn git_commit(repo: &Path, message_file: &Path, opts: CommitOptions) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.args([
+        "-C",
+        &repo.to_string_lossy(),
+        "commit",
+        "--cleanup=strip",
+        "-F",
+    ])
+    .arg(message_file);
+    if opts.amend {
+        cmd.arg("--amend");
+    }
+    if opts.signoff {
+        cmd.arg("--signoff");
+    }
+    if opts.no_verify {
+        cmd.arg("--no-verify");
+    }
+    let output = cmd.output().context("failed to run git commit")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let combined = format!("{}\n{}", stdout.trim(), stderr.trim());
+        bail!("{}", combined.trim());
+    }
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+/// Blame `path` at `rev`, or the working tree when `rev` is `None`.
+pub fn git_blame(repo: &Path, path: &str, rev: Option<&str>) -> Result<Vec<BlameLine>> {
+    let mut args = vec!["blame", "--porcelain"];
+    if let Some(rev) = rev {
+        args.push(rev);
+    }
+    args.extend(["--", path]);
+    let out = run_git(repo, &args)?;
+    Ok(parse_blame(&out))
+}
+
+/// Parse `git blame --porcelain` output.
+///
+/// Commit metadata is only printed the first time a commit appears, so it
+/// is cached by hash and reused for later lines from the same commit.
+fn parse_blame(out: &str) -> Vec<BlameLine> {
+    use std::collections::HashMap;
+
+    #[derive(Default, Clone)]
+    struct Meta {
+        author: String,
+        author_time: i64,
+        summary: String,
+        previous: Option<(String, String)>,
+    }
+
+    let mut cache: HashMap<String, Meta> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+
+    for line in out.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            if let Some((hash, line_no)) = current.take() {
+                let meta = cache.get(&hash).cloned().unwrap_or_default();
+                lines.push(BlameLine {
+                    hash,
+                    author: meta.author,
+                    author_time: meta.author_time,
+                    summary: meta.summary,
+                    line_no,
+                    content: content.to_string(),
+                    previous: meta.previous,
+                });
+            }
+            continue;
+        }
+
+        if current.is_none() {
+            // Header: <hash> <orig-line> <final-line> [<group-size>]
+            let mut parts = line.split(' ');
+            if let (Some(hash), Some(_), Some(final_line)) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                let line_no = final_line.parse().unwrap_or(0);
+                cache.entry(hash.to_string()).or_default();
+                current = Some((hash.to_string(), line_no));
+            }
+            continue;
+        }
+
+        let Some((hash, _)) = &current else { continue };
+        let meta = cache.entry(hash.clone()).or_default();
+        if let Some(v) = line.strip_prefix("author ") {
+            meta.author = v.to_string();
+        } else if let Some(v) = line.strip_prefix("author-time ") {
+            meta.author_time = v.parse().unwrap_or(0);
+        } else if let Some(v) = line.strip_prefix("summary ") {
+            meta.summary = v.to_string();
+        } else if let Some(v) = line.strip_prefix("previous ") {
+            if let Some((prev, path)) = v.split_once(' ') {
+                meta.previous = Some((prev.to_string(), path.to_string()));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Fetch metadata and the parsed diff of a single commit.
+///
+/// Merge commits are diffed against their first parent so the result is
+/// always a plain `diff --git` patch that `diff::parse_files` understands.
+pub fn git_commit_detail(repo: &Path, hash: &str) -> Result<CommitDetail> {
+    let meta = run_git(
+        repo,
+        &[
+            "show",
+            "-s",
+            "--date=iso",
+            "--format=%H%x00%P%x00%an%x00%ae%x00%ad%x00%cn%x00%ce%x00%cd%x00%(trailers:only,unfold)%x00%B",
+            hash,
+        ],
+    )?;
+    let raw_diff = run_git(
+        repo,
+        &["show", "--format=", "--diff-merges=first-parent", hash],
+    )?;
+    parse_commit_detail(&meta, &raw_diff)
+        .with_context(|| format!("unexpected git show output for {hash}"))
+}
+
+/// Parse the NUL-separated metadata produced by `git_commit_detail`.
+fn parse_commit_detail(meta: &str, raw_diff: &str) -> Option<CommitDetail> {
+    let parts: Vec<&str> = meta.splitn(10, '\0').collect();
+    if parts.len() != 10 {
+        return None;
+    }
+
+    let trailers: Vec<(String, String)> = parts[8]
+        .lines()
+        .filter_map(|l| l.split_once(": "))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    // %B repeats the trailer block at the end — drop it so it isn't shown twice
+    let mut message: Vec<&str> = parts[9].trim_end().lines().collect();
+    let trailer_lines: Vec<&str> = parts[8].lines().collect();
+    if !trailer_lines.is_empty() && message.ends_with(&trailer_lines) {
+        message.truncate(message.len() - trailer_lines.len());
+    }
+
+    Some(CommitDetail {
+        hash: parts[0].trim().to_string(),
+        parents: parts[1].split_whitespace().map(String::from).collect(),
+        author: parts[2].to_string(),
+        author_email: parts[3].to_string(),
+        author_date: parts[4].to_string(),
+        committer: parts[5].to_string(),
+        committer_email: parts[6].to_string(),
+        committer_date: parts[7].to_string(),
+        message: message.join("\n").trim_end().to_string(),
+        trailers,
+        files: diff::parse_files(raw_diff),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> String {
+        [
+            "abc123",
+            "p1 p2",
+            "Ann",
+            "ann@x",
+            "2024-01-01 10:00:00 +0000",
+            "Cid",
+            "cid@x",
+            "2024-01-02 10:00:00 +0000",
+            "Signed-off-by: Ann <ann@x>\n",
+            "Subject line\n\nBody text.\n\nSigned-off-by: Ann <ann@x>\n",
+        ]
+        .join("\0")
+    }
+
+    #[test]
+    fn status_entries() {
+        let out = [" M src/a.rs", "R  new.rs", "old.rs", "?? notes.txt", ""].join("\0");
+        let files = parse_status(&out);
+        let summary: Vec<(&str, char, char)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.index, f.worktree))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("src/a.rs", ' ', 'M'),
+                ("new.rs", 'R', ' '),
+                ("notes.txt", '?', '?')
+            ]
+        );
+    }
+
+    #[test]
+    fn numstat_entries() {
+        let out = ["3\t1\tsrc/a.rs", "-\t-\tlogo.png", ""].join("\0");
+        assert_eq!(
+            parse_numstat(&out),
+            [
+                ("src/a.rs".to_string(), 3, 1),
+                ("logo.png".to_string(), 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn commit_detail_fields() {
+        let d = parse_commit_detail(&meta(), "").unwrap();
+        assert_eq!(d.hash, "abc123");
+        assert_eq!(d.parents, vec!["p1", "p2"]);
+        assert_eq!(d.author, "Ann");
+        assert_eq!(d.committer_email, "cid@x");
+        assert!(d.files.is_empty());
+    }
+
+    #[test]
+    fn commit_detail_trailers_split_from_message() {
+        let d = parse_commit_detail(&meta(), "").unwrap();
+        assert_eq!(
+            d.trailers,
+            vec![("Signed-off-by".to_string(), "Ann <ann@x>".to_string())]
+        );
+        assert_eq!(d.message, "Subject line\n\nBody text.");
+    }
+
+    #[test]
+    fn commit_detail_parses_diff() {
+        let raw = "diff --git a/f.txt b/f.txt\n--- a/f.txt\n+++ b/f.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        let d = parse_commit_detail(&meta(), raw).unwrap();
+        assert_eq!(d.files.len(), 1);
+        assert_eq!(d.files[0].filename, "f.txt");
+    }
+
+    #[test]
+    fn log_entry_with_refs() {
+        let out = [
+            "abc1234",
+            "abc1234ffff",
+            "Fix it",
+            "Ann",
+            "2 days ago",
+            "2024-01-01 10:00",
+            "HEAD -> main, tag: v1",
+            "p1 p2",
+        ]
+        .join("\0");
+        let entries = parse_log(&out);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].full_hash, "abc1234ffff");
+        assert_eq!(entries[0].refs, "HEAD -> main, tag: v1");
+        assert_eq!(entries[0].date_absolute, "2024-01-01 10:00");
+        assert_eq!(entries[0].parents, vec!["p1", "p2"]);
+    }
+
+    #[test]
+    fn log_search_prefixes() {
+        assert_eq!(
+            LogSearch::parse("author: ann"),
+            Some(LogSearch::Author("ann".into()))
+        );
+        assert_eq!(
+            LogSearch::parse("code:parse_files"),
+            Some(LogSearch::Pickaxe("parse_files".into()))
+        );
+        assert_eq!(
+            LogSearch::parse("fix bug"),
+            Some(LogSearch::Message("fix bug".into()))
+        );
+        assert_eq!(LogSearch::parse("   "), None);
+        // A bare `-S` is a git error
+        assert_eq!(LogSearch::parse("code: "), None);
+    }
+
+    #[test]
+    fn log_filter_round_trip() {
+        let f = LogFilter::parse("author=ann since=2.weeks path=src/app.rs all").unwrap();
+        assert_eq!(f.author.as_deref(), Some("ann"));
+        assert_eq!(f.since.as_deref(), Some("2.weeks"));
+        assert_eq!(f.path.as_deref(), Some("src/app.rs"));
+        assert!(f.all);
+        assert_eq!(LogFilter::parse(&f.to_string()).unwrap(), f);
+
+        let f = LogFilter::parse(r#"author="Jane Doe" path='my dir/'"#).unwrap();
+        assert_eq!(f.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(f.path.as_deref(), Some("my dir/"));
+        assert_eq!(LogFilter::parse(&f.to_string()).unwrap(), f);
+        assert!(LogFilter::parse(r#"author="Jane"#).is_err());
+    }
+
+    #[test]
+    fn log_filter_rejects_unknown_keys() {
+        assert!(LogFilter::parse("colour=red").is_err());
+        assert!(LogFilter::parse("alice").is_err());
+        // Would reach git as an option
+        assert!(LogFilter::parse("branch=--output=/tmp/x").is_err());
+        assert!(LogFilter::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn blame_reuses_commit_metadata() {
+        let out = "\
+aaaa 1 1 2
+author Ann
+author-time 100
+summary First
+previous bbbb old.rs
+filename f.rs
+\tfn main() {
+aaaa 2 2
+\t}
+0000 3 3 1
+author Not Committed Yet
+author-time 200
+summary Version of f.rs from f.rs
+filename f.rs
+\t// new
+";
+        let lines = parse_blame(out);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].author, "Ann");
+        assert_eq!(lines[1].content, "}");
+        assert_eq!(lines[1].line_no, 2);
+        assert_eq!(
+            lines[0].previous,
+            Some(("bbbb".to_string(), "old.rs".to_string()))
+        );
+        assert!(lines[2].is_uncommitted());
+        assert!(!lines[0].is_uncommitted());
+    }
+
+    #[test]
+    fn commit_detail_rejects_truncated_output() {
+        assert!(parse_commit_detail("abc\0def", "").is_none());
+    }
+}
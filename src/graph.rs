@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+
+use crate::git::CommitEntry;
+
+/// One glyph of a commit graph row, tagged with its lane for colouring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphCell {
+    pub glyph: char,
+    pub lane: usize,
+}
+
+/// Graph cells for one commit row — two cells per lane (glyph + connector).
+pub type GraphRow = Vec<GraphCell>;
+
+/// Lay out `git log --graph`-style lanes for a list of commits.
+///
+/// Each lane tracks the hash it expects to see next. A commit takes the lane
+/// waiting for it (or a free one if it's a branch tip), other lanes waiting
+/// for the same commit merge into it, and extra parents of a merge commit
+/// fork off into new or existing lanes. Everything is drawn in a single row
+/// per commit so rows stay aligned with the commit list.
+///
+/// Parents missing from `commits` usually sit on a page not loaded yet, so
+/// their lanes run on to the bottom. With `skips_parents` the log leaves
+/// out commits its parents point at, and lanes only lead to listed commits;
+/// otherwise every skipped parent would hold a lane forever.
+pub fn build(commits: &[CommitEntry], skips_parents: bool) -> Vec<GraphRow> {
+    let mut lanes: Vec<Option<String>> = Vec::new();
+    let mut rows = Vec::with_capacity(commits.len());
+    let listed: HashSet<&str> = commits.iter().map(|c| c.full_hash.as_str()).collect();
+
+    for commit in commits {
+        let parents: Vec<&String> = commit
+            .parents
+            .iter()
+            .filter(|p| !skips_parents || listed.contains(p.as_str()))
+            .collect();
+        let hash = Some(commit.full_hash.as_str());
+        let col = match lanes.iter().position(|l| l.as_deref() == hash) {
+            Some(col) => col,
+            None => alloc_lane(&mut lanes),
+        };
+        let before: Vec<bool> = lanes.iter().map(Option::is_some).collect();
+
+        // Other lanes waiting for this commit converge into it
+        let merging: Vec<usize> = (0..lanes.len())
+            .filter(|&i| i != col && lanes[i].as_deref() == hash)
+            .collect();
+        for &i in &merging {
+            lanes[i] = None;
+        }
+
+        lanes[col] = parents.first().map(|p| p.to_string());
+
+        // Extra parents of a merge fork into their own lanes
+        let mut forks: Vec<(usize, bool)> = Vec::new(); // (lane, lane already existed)
+        for &parent in parents.iter().skip(1) {
+            match lanes.iter().position(|l| l.as_ref() == Some(parent)) {
+                Some(j) => forks.push((j, true)),
+                None => {
+                    let j = alloc_lane(&mut lanes);
+                    lanes[j] = Some(parent.clone());
+                    forks.push((j, false));
+                }
+            }
+        }
+
+        rows.push(render_row(col, &before, &merging, &forks, lanes.len()));
+
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+    }
+
+    rows
+}
+
+/// Return the first free lane, growing the lane list if needed.
+fn alloc_lane(lanes: &mut Vec<Option<String>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(i) => i,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+fn render_row(
+    col: usize,
+    before: &[bool],
+    merging: &[usize],
+    forks: &[(usize, bool)],
+    lane_count: usize,
+) -> GraphRow {
+    let width = lane_count.max(before.len());
+    let passing = |i: usize| before.get(i).copied().unwrap_or(false);
+
+    // Horizontal spans between the commit lane and every merge/fork lane
+    let spans: Vec<(usize, usize)> = merging
+        .iter()
+        .chain(forks.iter().map(|(j, _)| j))
+        .map(|&j| (j.min(col), j.max(col)))
+        .collect();
+    let crossed = |i: usize| spans.iter().any(|&(lo, hi)| lo < i && i < hi);
+    let joined_right = |i: usize| spans.iter().any(|&(lo, hi)| lo <= i && i < hi);
+
+    let mut row = Vec::with_capacity(width * 2);
+    for i in 0..width {
+        let glyph = if i == col {
+            '●'
+        } else if merging.contains(&i) {
+            if i < col {
+                '└'
+            } else {
+                '┘'
+            }
+        } else if let Some(&(_, existed)) = forks.iter().find(|(j, _)| *j == i) {
+            match (i < col, existed) {
+                (true, true) => '├',
+                (false, true) => '┤',
+                (true, false) => '┌',
+                (false, false) => '┐',
+            }
+        } else if passing(i) {
+            if crossed(i) {
+                '┼'
+            } else {
+                '│'
+            }
+        } else if crossed(i) {
+            '─'
+        } else {
+            ' '
+        };
+        row.push(GraphCell { glyph, lane: i });
+
+        let connector = if joined_right(i) { '─' } else { ' ' };
+        row.push(GraphCell {
+            glyph: connector,
+            lane: i,
+        });
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, parents: &[&str]) -> CommitEntry {
+        CommitEntry {
+            hash: hash.to_string(),
+            full_hash: hash.to_string(),
+            message: String::new(),
+            author: String::new(),
+            date_relative: String::new(),
+            date_absolute: String::new(),
+            refs: String::new(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn glyphs(row: &GraphRow) -> String {
+        row.iter()
+            .map(|c| c.glyph)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn linear_history_is_one_lane() {
+        let rows = build(
+            &[commit("c", &["b"]), commit("b", &["a"]), commit("a", &[])],
+            false,
+        );
+        assert!(rows.iter().all(|r| glyphs(r) == "●"));
+    }
+
+    #[test]
+    fn merge_forks_and_rejoins() {
+        // m merges topic (t) into main (b); both descend from a
+        let rows = build(
+            &[
+                commit("m", &["b", "t"]),
+                commit("t", &["a"]),
+                commit("b", &["a"]),
+                commit("a", &[]),
+            ],
+            false,
+        );
+        let rows: Vec<String> = rows.iter().map(glyphs).collect();
+        assert_eq!(rows, vec!["●─┐", "│ ●", "● │", "●─┘"]);
+    }
+
+    #[test]
+    fn parents_left_out_of_the_list() {
+        // An author filter: every other commit, and the merge parent x, are
+        // not listed
+        let commits = [
+            commit("e", &["d"]),
+            commit("c", &["b", "x"]),
+            commit("a", &["z"]),
+        ];
+        let rows: Vec<String> = build(&commits, true).iter().map(glyphs).collect();
+        assert_eq!(rows, vec!["●", "●", "●"]);
+        // The next page of an unfiltered log may still bring them
+        let rows: Vec<String> = build(&commits, false).iter().map(glyphs).collect();
+        assert_eq!(rows, vec!["●", "│ ●─┐", "│ │ │ ●"]);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Entries kept per history file.
+const MAX_ENTRIES: usize = 200;
+
+/// Previously entered prompt lines, oldest first, browsed with Up/Down.
+///
+/// Backed by a plain file with one entry per line, if `path` is set.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    /// Entry being shown while browsing; `None` when editing a new line.
+    cursor: Option<usize>,
+    /// Line being typed when browsing started, restored past the newest.
+    draft: String,
+}
+
+impl History {
+    /// Load `path`; a missing or unreadable file starts an empty history.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|text| text.lines().map(String::from).collect())
+            .unwrap_or_default();
+        Self {
+            entries,
+            path,
+            ..Self::default()
+        }
+    }
+
+    /// Default file for `name`: `$XDG_STATE_HOME/git-monitor/<name>`,
+    /// falling back to `~/.local/state`.
+    pub fn default_path(name: &str) -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))?;
+        Some(base.join("git-monitor").join(name))
+    }
+
+    /// Record `entry` as the newest, dropping an earlier copy, and save.
+    pub fn push(&mut self, entry: &str) {
+        self.reset();
+        if entry.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != entry);
+        self.entries.push(entry.to_string());
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+        // History is a convenience; a read-only state dir shouldn't get
+        // in the way of searching
+        let _ = self.save();
+    }
+
+    /// Step to an older entry. `current` is the line being edited, kept
+    /// to come back to.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let index = match self.cursor {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(i) => i.saturating_sub(1),
+        };
+        self.cursor = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// Step to a newer entry, or back to the draft past the newest.
+    pub fn newer(&mut self) -> Option<&str> {
+        let i = self.cursor?;
+        if i + 1 < self.entries.len() {
+            self.cursor = Some(i + 1);
+            Some(&self.entries[i + 1])
+        } else {
+            self.cursor = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Stop browsing, e.g. when the line is edited.
+    pub fn reset(&mut self) {
+        self.cursor = None;
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browse_and_return_to_draft() {
+        let mut history = History::default();
+        history.push("one");
+        history.push("two");
+        history.push("one");
+        assert_eq!(history.older("dra"), Some("one"));
+        assert_eq!(history.older("one"), Some("two"));
+        assert_eq!(history.older("two"), Some("two"));
+        assert_eq!(history.newer(), Some("one"));
+        assert_eq!(history.newer(), Some("dra"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn persists_to_file() {
+        let path = std::env::temp_dir()
+            .join(format!("git-monitor-history-{}", std::process::id()))
+            .join("search");
+        let mut history = History::load(Some(path.clone()));
+        history.push("needle");
+        history.push("  ");
+        let mut reloaded = History::load(Some(path.clone()));
+        assert_eq!(reloaded.older(""), Some("needle"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
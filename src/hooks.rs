@@ -0,0 +1,326 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::diff::FileDiff;
+use crate::event::AppEvent;
+use crate::git::RepoState;
+use crate::pathspec::PathFilter;
+use crate::process::{self, Pid};
+
+/// How long a hook may run when its config sets no `timeout_secs`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What makes a hook run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    /// Working tree files changed on disk.
+    #[default]
+    Change,
+    /// The staged diff changed, e.g. after `git add` or a commit.
+    Staged,
+    /// The unstaged diff changed.
+    Unstaged,
+}
+
+/// A `[[hooks]]` entry from the config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    /// Label for the status bar and the output panel; defaults to the command.
+    pub name: Option<String>,
+    /// Shell command run from the repository root. `{files}` is replaced
+    /// with the files that triggered it, quoted for the shell.
+    pub command: String,
+    /// Only run when one of the triggering files matches these paths or globs.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub on: Trigger,
+    /// Stop a run after this many seconds, along with everything it started.
+    pub timeout_secs: Option<u64>,
+}
+
+impl HookConfig {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout_secs
+            .map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+    }
+}
+
+/// How one hook run ended.
+#[derive(Debug, Clone)]
+pub struct HookResult {
+    /// Index of the hook in the config.
+    pub hook: usize,
+    /// Exit code; `None` if the command was killed or could not start.
+    pub code: Option<i32>,
+    pub success: bool,
+    /// Standard output and error, interleaved.
+    pub output: String,
+    pub duration: Duration,
+    pub finished: Instant,
+    /// The timeout, if the run was stopped for exceeding it.
+    pub timed_out: Option<Duration>,
+}
+
+/// A hook and the state of its runs.
+#[derive(Debug)]
+struct Hook {
+    config: HookConfig,
+    filter: PathFilter,
+    /// Process id of the run in progress.
+    running: Option<Pid>,
+    /// Files of triggers that arrived while it was running; it runs again
+    /// on them once the current run finishes.
+    pending: Option<Vec<String>>,
+    last: Option<HookResult>,
+}
+
+/// The configured hooks and their latest results.
+#[derive(Debug, Default)]
+pub struct Hooks {
+    hooks: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn new(configs: &[HookConfig]) -> Self {
+        let hooks = configs
+            .iter()
+            .map(|config| Hook {
+                // Validated when the config was loaded
+                filter: PathFilter::new(config.paths.clone()).unwrap_or_default(),
+                config: config.clone(),
+                running: None,
+                pending: None,
+                last: None,
+            })
+            .collect();
+        Self { hooks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Hooks a refresh from `old` to `new` triggers, each with the files
+    /// that triggered it. `changed` are the working tree files the
+    /// watcher reported.
+    pub fn triggered(
+        &self,
+        changed: &[PathBuf],
+        old: &RepoState,
+        new: &RepoState,
+    ) -> Vec<(usize, Vec<String>)> {
+        self.hooks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, hook)| {
+                let files = match hook.config.on {
+                    Trigger::Change => changed
+                        .iter()
+                        .map(|p| p.to_string_lossy().into_owned())
+                        .collect(),
+                    Trigger::Staged => changed_files(&old.staged_diff, &new.staged_diff),
+                    Trigger::Unstaged => changed_files(&old.unstaged_diff, &new.unstaged_diff),
+                };
+                let files: Vec<String> = files
+                    .into_iter()
+                    .filter(|f| hook.filter.matches(Path::new(f)))
+                    .collect();
+                (!files.is_empty()).then_some((i, files))
+            })
+            .collect()
+    }
+
+    /// Run hook `i` on `files` in the background, or queue the files if it
+    /// is still running. The result arrives as `AppEvent::HookDone`.
+    pub fn start(&mut self, i: usize, files: Vec<String>, repo: &Path, tx: &Sender<AppEvent>) {
+        let Some(hook) = self.hooks.get_mut(i) else {
+            return;
+        };
+        if hook.running.is_some() {
+            let pending = hook.pending.get_or_insert_with(Vec::new);
+            for file in files {
+                if !pending.contains(&file) {
+                    pending.push(file);
+                }
+            }
+            return;
+        }
+        match spawn(i, &hook.config, files, repo, tx.clone()) {
+            Ok(pid) => hook.running = Some(pid),
+            Err(e) => {
+                hook.last = Some(HookResult {
+                    hook: i,
+                    code: None,
+                    success: false,
+                    output: format!("cannot run sh: {e}"),
+                    duration: Duration::ZERO,
+                    finished: Instant::now(),
+                    timed_out: None,
+                });
+            }
+        }
+    }
+
+    /// Stop every run in progress, along with everything it started.
+    pub fn cancel(&mut self) {
+        for hook in &mut self.hooks {
+            if let Some(pid) = hook.running.take() {
+                process::kill(&pid);
+            }
+        }
+    }
+
+    /// Record a finished run and start the queued one, if any.
+    pub fn finish(&mut self, result: HookResult, repo: &Path, tx: &Sender<AppEvent>) {
+        let i = result.hook;
+        let Some(hook) = self.hooks.get_mut(i) else {
+            return;
+        };
+        hook.running = None;
+        hook.last = Some(result);
+        if let Some(files) = hook.pending.take() {
+            self.start(i, files, repo, tx);
+        }
+    }
+
+    /// Name, whether it is running and the latest result of every hook.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool, Option<&HookResult>)> {
+        self.hooks
+            .iter()
+            .map(|h| (h.config.name(), h.running.is_some(), h.last.as_ref()))
+    }
+
+    /// The run that finished last, with its hook's name.
+    pub fn latest(&self) -> Option<(&str, &HookResult)> {
+        self.hooks
+            .iter()
+            .filter_map(|h| Some((h.config.name(), h.last.as_ref()?)))
+            .max_by_key(|(_, result)| result.finished)
+    }
+}
+
+/// Files whose diff differs between `old` and `new`, including files that
+/// dropped out of it.
+fn changed_files(old: &[FileDiff], new: &[FileDiff]) -> Vec<String> {
+    let same = |a: &FileDiff, b: &FileDiff| {
+        a.lines.len() == b.lines.len()
+            && a.lines
+                .iter()
+                .zip(&b.lines)
+                .all(|(x, y)| x.text() == y.text())
+    };
+    let mut files: Vec<String> = new
+        .iter()
+        .filter(|fd| !old.iter().any(|o| o.filename == fd.filename && same(o, fd)))
+        .map(|fd| fd.filename.clone())
+        .collect();
+    files.extend(
+        old.iter()
+            .filter(|o| !new.iter().any(|fd| fd.filename == o.filename))
+            .map(|o| o.filename.clone()),
+    );
+    // Placeholders have no filename
+    files.retain(|f| !f.is_empty());
+    files
+}
+
+/// Replace `{files}` in `command` with the files, each quoted for `sh`.
+fn expand(command: &str, files: &[String]) -> String {
+    let quoted: Vec<String> = files
+        .iter()
+        .map(|f| format!("'{}'", f.replace('\'', r"'\''")))
+        .collect();
+    command.replace("{files}", &quoted.join(" "))
+}
+
+/// Run the hook's command through `sh` in a process group of its own,
+/// stopped after its timeout. The files are also passed in
+/// `GIT_MONITOR_FILES`, one per line.
+fn spawn(
+    hook: usize,
+    config: &HookConfig,
+    files: Vec<String>,
+    repo: &Path,
+    tx: Sender<AppEvent>,
+) -> Result<Pid> {
+    let mut cmd = process::shell(&expand(&config.command, &files), repo);
+    cmd.env("GIT_MONITOR_FILES", files.join("\n"));
+    let timeout = config.timeout();
+    process::spawn(cmd, Some(timeout), move |exit| {
+        let _ = tx.send(AppEvent::HookDone(HookResult {
+            hook,
+            code: exit.code,
+            success: exit.success,
+            output: exit.output,
+            duration: exit.duration,
+            finished: Instant::now(),
+            timed_out: exit.timed_out.then_some(timeout),
+        }));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::parse_files;
+
+    fn hooks(toml: &str) -> Hooks {
+        #[derive(Deserialize)]
+        struct File {
+            hooks: Vec<HookConfig>,
+        }
+        let file: File = toml::from_str(toml).unwrap();
+        Hooks::new(&file.hooks)
+    }
+
+    #[test]
+    fn triggers_filter_by_kind_and_path() {
+        let hooks = hooks(
+            r#"
+            [[hooks]]
+            command = "cargo check"
+            paths = ["*.rs"]
+
+            [[hooks]]
+            command = "echo staged"
+            on = "staged"
+            "#,
+        );
+        let old = RepoState::empty("");
+        let mut new = RepoState::empty("");
+        new.staged_diff = parse_files("diff --git a/a.md b/a.md\n@@ -1 +1 @@\n-a\n+b\n");
+
+        let changed = [PathBuf::from("README.md"), PathBuf::from("src/main.rs")];
+        let triggered = hooks.triggered(&changed, &old, &new);
+        assert_eq!(
+            triggered,
+            [
+                (0, vec!["src/main.rs".to_string()]),
+                (1, vec!["a.md".to_string()])
+            ]
+        );
+        // Nothing staged changed and no Rust file was saved
+        let triggered = hooks.triggered(&changed[..1], &new, &new);
+        assert!(triggered.is_empty());
+    }
+
+    #[test]
+    fn files_are_quoted() {
+        let files = ["a b.rs".to_string(), "it's.rs".to_string()];
+        assert_eq!(
+            expand("rustfmt --check {files}", &files),
+            r"rustfmt --check 'a b.rs' 'it'\''s.rs'"
+        );
+    }
+}
@@ -0,0 +1,793 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::Screen;
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Back,
+    Help,
+    ToggleMouse,
+    CommandPrompt,
+    ToggleView,
+    ScrollDown,
+    ScrollUp,
+    ScrollTop,
+    ScrollBottom,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    NextFile,
+    PrevFile,
+    LastChange,
+    ToggleFollow,
+    ToggleSidebar,
+    FocusPane,
+    EditPathFilter,
+    ToggleFold,
+    FoldAll,
+    UnfoldAll,
+    VisualSelect,
+    Yank,
+    YankFile,
+    SearchForward,
+    SearchBackward,
+    SearchNext,
+    SearchPrev,
+    ClearSearch,
+    Open,
+    Pager,
+    CommitLog,
+    FileLog,
+    Blame,
+    ComposeCommit,
+    ToggleFullHash,
+    ToggleAbsoluteDate,
+    ToggleRefs,
+    ToggleGraph,
+    ToggleAllRefs,
+    EditFilter,
+    HistorySearch,
+    BlameParent,
+    BlameBack,
+    Timeline,
+    OlderSnapshot,
+    NewerSnapshot,
+    MarkSnapshot,
+    ToggleHooks,
+    ToggleChecks,
+    NextCheckRef,
+    RunCheck,
+    EditMessage,
+    ToggleAmend,
+    ToggleSignoff,
+    ToggleNoVerify,
+    Commit,
+}
+
+impl Action {
+    pub const ALL: [Action; 60] = [
+        Action::Quit,
+        Action::Back,
+        Action::Help,
+        Action::ToggleMouse,
+        Action::CommandPrompt,
+        Action::ToggleView,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::ScrollTop,
+        Action::ScrollBottom,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::NextFile,
+        Action::PrevFile,
+        Action::LastChange,
+        Action::ToggleFollow,
+        Action::ToggleSidebar,
+        Action::FocusPane,
+        Action::EditPathFilter,
+        Action::ToggleFold,
+        Action::FoldAll,
+        Action::UnfoldAll,
+        Action::VisualSelect,
+        Action::Yank,
+        Action::YankFile,
+        Action::SearchForward,
+        Action::SearchBackward,
+        Action::SearchNext,
+        Action::SearchPrev,
+        Action::ClearSearch,
+        Action::Open,
+        Action::Pager,
+        Action::CommitLog,
+        Action::FileLog,
+        Action::Blame,
+        Action::ComposeCommit,
+        Action::ToggleFullHash,
+        Action::ToggleAbsoluteDate,
+        Action::ToggleRefs,
+        Action::ToggleGraph,
+        Action::ToggleAllRefs,
+        Action::EditFilter,
+        Action::HistorySearch,
+        Action::BlameParent,
+        Action::BlameBack,
+        Action::Timeline,
+        Action::OlderSnapshot,
+        Action::NewerSnapshot,
+        Action::MarkSnapshot,
+        Action::ToggleHooks,
+        Action::ToggleChecks,
+        Action::NextCheckRef,
+        Action::RunCheck,
+        Action::EditMessage,
+        Action::ToggleAmend,
+        Action::ToggleSignoff,
+        Action::ToggleNoVerify,
+        Action::Commit,
+    ];
+
+    /// Name used in the `[keys.*]` config tables.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::Help => "help",
+            Action::ToggleMouse => "toggle_mouse",
+            Action::CommandPrompt => "command_prompt",
+            Action::ToggleView => "toggle_view",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollTop => "scroll_top",
+            Action::ScrollBottom => "scroll_bottom",
+            Action::HalfPageDown => "half_page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::NextFile => "next_file",
+            Action::PrevFile => "prev_file",
+            Action::LastChange => "last_change",
+            Action::ToggleFollow => "toggle_follow",
+            Action::ToggleSidebar => "toggle_sidebar",
+            Action::FocusPane => "focus_pane",
+            Action::EditPathFilter => "edit_path_filter",
+            Action::ToggleFold => "toggle_fold",
+            Action::FoldAll => "fold_all",
+            Action::UnfoldAll => "unfold_all",
+            Action::VisualSelect => "visual_select",
+            Action::Yank => "yank",
+            Action::YankFile => "yank_file",
+            Action::SearchForward => "search_forward",
+            Action::SearchBackward => "search_backward",
+            Action::SearchNext => "search_next",
+            Action::SearchPrev => "search_prev",
+            Action::ClearSearch => "clear_search",
+            Action::Open => "open",
+            Action::Pager => "pager",
+            Action::CommitLog => "commit_log",
+            Action::FileLog => "file_log",
+            Action::Blame => "blame",
+            Action::ComposeCommit => "compose_commit",
+            Action::ToggleFullHash => "toggle_full_hash",
+            Action::ToggleAbsoluteDate => "toggle_absolute_date",
+            Action::ToggleRefs => "toggle_refs",
+            Action::ToggleGraph => "toggle_graph",
+            Action::ToggleAllRefs => "toggle_all_refs",
+            Action::EditFilter => "edit_filter",
+            Action::HistorySearch => "history_search",
+            Action::BlameParent => "blame_parent",
+            Action::BlameBack => "blame_back",
+            Action::Timeline => "timeline",
+            Action::OlderSnapshot => "older_snapshot",
+            Action::NewerSnapshot => "newer_snapshot",
+            Action::MarkSnapshot => "mark_snapshot",
+            Action::ToggleHooks => "toggle_hooks",
+            Action::ToggleChecks => "toggle_checks",
+            Action::NextCheckRef => "next_check_ref",
+            Action::RunCheck => "run_check",
+            Action::EditMessage => "edit_message",
+            Action::ToggleAmend => "toggle_amend",
+            Action::ToggleSignoff => "toggle_signoff",
+            Action::ToggleNoVerify => "toggle_no_verify",
+            Action::Commit => "commit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// One-line description for the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Back => "Go back / cancel",
+            Action::Help => "Show this help",
+            Action::ToggleMouse => "Toggle mouse capture",
+            Action::CommandPrompt => "Command prompt (:base, :filter, :log, :w, :yank)",
+            Action::ToggleView => "Toggle staged / unstaged",
+            Action::ScrollDown => "Down one line",
+            Action::ScrollUp => "Up one line",
+            Action::ScrollTop => "Go to top",
+            Action::ScrollBottom => "Go to bottom",
+            Action::HalfPageDown => "Half page down",
+            Action::HalfPageUp => "Half page up",
+            Action::PageDown => "Full page down",
+            Action::PageUp => "Full page up",
+            Action::NextFile => "Next file",
+            Action::PrevFile => "Previous file",
+            Action::LastChange => "Jump to the latest change",
+            Action::ToggleFollow => "Toggle following changes",
+            Action::ToggleSidebar => "Toggle file tree",
+            Action::FocusPane => "Switch focus between file tree and diff",
+            Action::EditPathFilter => "Restrict the diff to paths or globs",
+            Action::ToggleFold => "Fold / unfold file",
+            Action::FoldAll => "Fold all files",
+            Action::UnfoldAll => "Unfold all files",
+            Action::VisualSelect => "Start / stop selecting lines",
+            Action::Yank => "Copy the selection or hunk as a patch",
+            Action::YankFile => "Copy the file as a patch",
+            Action::SearchForward => "Search forward",
+            Action::SearchBackward => "Search backward",
+            Action::SearchNext => "Next match",
+            Action::SearchPrev => "Previous match",
+            Action::ClearSearch => "Clear search",
+            Action::Open => "Open commit",
+            Action::Pager => "Open in pager",
+            Action::CommitLog => "Commit log",
+            Action::FileLog => "Commit log for this file",
+            Action::Blame => "Blame file under cursor",
+            Action::ComposeCommit => "Commit staged changes",
+            Action::ToggleFullHash => "Toggle full hashes",
+            Action::ToggleAbsoluteDate => "Toggle absolute dates",
+            Action::ToggleRefs => "Toggle refs column",
+            Action::ToggleGraph => "Toggle commit graph",
+            Action::ToggleAllRefs => "Toggle all branches",
+            Action::EditFilter => "Filter by path, author, date, branch",
+            Action::HistorySearch => "Search whole history",
+            Action::BlameParent => "Blame before this commit",
+            Action::BlameBack => "Back to previous blame",
+            Action::Timeline => "Session timeline of working tree snapshots",
+            Action::OlderSnapshot => "Older snapshot",
+            Action::NewerSnapshot => "Newer snapshot",
+            Action::MarkSnapshot => "Mark snapshot to compare with",
+            Action::ToggleHooks => "Toggle the hook output panel",
+            Action::ToggleChecks => "Toggle the check panel",
+            Action::NextCheckRef => "Jump to the next file:line of the check output",
+            Action::RunCheck => "Run the check now",
+            Action::EditMessage => "Edit message in $EDITOR",
+            Action::ToggleAmend => "Toggle amend",
+            Action::ToggleSignoff => "Toggle sign-off",
+            Action::ToggleNoVerify => "Toggle --no-verify",
+            Action::Commit => "Create the commit",
+        }
+    }
+
+    /// Help overlay section this action is listed under.
+    pub fn category(self) -> &'static str {
+        use Action::*;
+        match self {
+            Quit | Back | Help | ToggleMouse | CommandPrompt | ToggleView | Open | Pager => {
+                "General"
+            }
+            ScrollDown | ScrollUp | ScrollTop | ScrollBottom | HalfPageDown | HalfPageUp
+            | PageDown | PageUp => "Navigation",
+            NextFile | PrevFile | LastChange | ToggleFollow | ToggleSidebar | FocusPane
+            | EditPathFilter | ToggleFold | FoldAll | UnfoldAll | VisualSelect | Yank
+            | YankFile => "Files",
+            SearchForward | SearchBackward | SearchNext | SearchPrev | ClearSearch
+            | HistorySearch => "Search",
+            CommitLog | FileLog | Blame | BlameParent | BlameBack => "History",
+            Timeline | OlderSnapshot | NewerSnapshot | MarkSnapshot => "Timeline",
+            ToggleHooks | ToggleChecks | NextCheckRef | RunCheck => "Tools",
+            ToggleFullHash | ToggleAbsoluteDate | ToggleRefs | ToggleGraph | ToggleAllRefs
+            | EditFilter => "Log view",
+            ComposeCommit | EditMessage | ToggleAmend | ToggleSignoff | ToggleNoVerify | Commit => {
+                "Commit"
+            }
+        }
+    }
+
+    /// Whether a count prefix (`5j`) repeats this action.
+    pub fn repeatable(self) -> bool {
+        matches!(
+            self,
+            Action::ScrollDown
+                | Action::ScrollUp
+                | Action::HalfPageDown
+                | Action::HalfPageUp
+                | Action::PageDown
+                | Action::PageUp
+                | Action::NextFile
+                | Action::PrevFile
+                | Action::SearchNext
+                | Action::SearchPrev
+        )
+    }
+}
+
+/// A single key press, normalised so bindings compare reliably.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character for `G`, `?`, `]`, ...
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl std::fmt::Display for KeyPress {
+    /// Compact notation for the help bar: `j`, `C-d`, `Tab`, `Space`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            KeyCode::Backspace => write!(f, "BS"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Parse one binding, e.g. `j`, `gg`, `ctrl-d`, `pagedown`, `g t`.
+///
+/// Whitespace separates keys. A token that is a named key or has a
+/// modifier prefix is a single key; any other token is a run of
+/// characters, so `gg` is `g` followed by `g`.
+pub fn parse_binding(spec: &str) -> Result<Vec<KeyPress>> {
+    let mut keys = Vec::new();
+    for token in spec.split_whitespace() {
+        if let Some(key) = parse_named_key(token)? {
+            keys.push(key);
+        } else {
+            keys.extend(
+                token
+                    .chars()
+                    .map(|c| KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE)),
+            );
+        }
+    }
+    if keys.is_empty() {
+        bail!("empty key binding");
+    }
+    Ok(keys)
+}
+
+/// Parse a named or modified key; `Ok(None)` for a plain character run.
+fn parse_named_key(token: &str) -> Result<Option<KeyPress>> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = token;
+    loop {
+        let lower = rest.to_lowercase();
+        let prefix = ["ctrl-", "c-", "alt-", "m-", "shift-", "s-"]
+            .into_iter()
+            .find(|p| lower.starts_with(p) && rest.len() > p.len());
+        let Some(prefix) = prefix else { break };
+        modifiers |= match prefix {
+            "ctrl-" | "c-" => KeyModifiers::CONTROL,
+            "alt-" | "m-" => KeyModifiers::ALT,
+            _ => KeyModifiers::SHIFT,
+        };
+        rest = &rest[prefix.len()..];
+    }
+
+    let code = match rest.to_lowercase().as_str() {
+        "enter" | "return" | "cr" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "space" => KeyCode::Char(' '),
+        "backspace" | "bs" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+            KeyCode::F(f[1..].parse().unwrap_or(1))
+        }
+        _ if modifiers.is_empty() => return Ok(None),
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => bail!("unknown key `{token}`"),
+            }
+        }
+    };
+    Ok(Some(KeyPress::new(code, modifiers)))
+}
+
+/// Help overlay section order.
+const CATEGORIES: [&str; 9] = [
+    "General",
+    "Navigation",
+    "Files",
+    "Search",
+    "History",
+    "Timeline",
+    "Log view",
+    "Commit",
+    "Tools",
+];
+
+/// One help overlay section: a category and its `(keys, description)` rows.
+pub type HelpSection = (&'static str, Vec<(String, &'static str)>);
+
+/// Outcome of feeding the pending key sequence to a keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Action(Action),
+    /// The keys so far are a prefix of a longer binding — wait for more.
+    Pending,
+    NoMatch,
+}
+
+/// Key bindings for every screen.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<Screen, Vec<(Vec<KeyPress>, Action)>>,
+}
+
+/// Screens that have their own `[keys.<name>]` table.
+const SCREENS: [(&str, Screen); 6] = [
+    ("diff", Screen::Diff),
+    ("log", Screen::CommitLog),
+    ("detail", Screen::CommitDetail),
+    ("blame", Screen::Blame),
+    ("timeline", Screen::Timeline),
+    ("commit", Screen::Commit),
+];
+
+/// Default keys for a group of actions.
+type Defaults = &'static [(Action, &'static [&'static str])];
+
+/// Per-screen `action = ["key", ...]` overrides from the config.
+pub type KeyOverrides = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        use Action::*;
+
+        let nav: Defaults = &[
+            (ScrollDown, &["j", "down"]),
+            (ScrollUp, &["k", "up"]),
+            (ScrollTop, &["gg", "home"]),
+            (ScrollBottom, &["G", "end"]),
+            (HalfPageDown, &["ctrl-d"]),
+            (HalfPageUp, &["ctrl-u"]),
+            (PageDown, &["ctrl-f", "pagedown"]),
+            (PageUp, &["ctrl-b", "pageup"]),
+        ];
+        let files: Defaults = &[
+            (NextFile, &["]"]),
+            (PrevFile, &["["]),
+            (ToggleFold, &["space"]),
+            (FoldAll, &["C"]),
+            (UnfoldAll, &["E"]),
+            (VisualSelect, &["V"]),
+            (Yank, &["y"]),
+            (YankFile, &["Y"]),
+        ];
+        let search: Defaults = &[
+            (SearchForward, &["/"]),
+            (SearchBackward, &["?"]),
+            (SearchNext, &["n"]),
+            (SearchPrev, &["N"]),
+        ];
+
+        let general: Defaults = &[
+            (Help, &["f1", "h"]),
+            (ToggleMouse, &["M"]),
+            (CommandPrompt, &[":"]),
+        ];
+
+        let diff: Defaults = &[
+            (Quit, &["q", "ctrl-c"]),
+            (ToggleView, &["tab"]),
+            (ClearSearch, &["esc"]),
+            (Open, &["enter"]),
+            (ToggleSidebar, &["t"]),
+            (FocusPane, &["ctrl-w"]),
+            (EditPathFilter, &["P"]),
+            (Pager, &["d"]),
+            (ComposeCommit, &["c"]),
+            (Blame, &["b"]),
+            (CommitLog, &["l"]),
+            (FileLog, &["L"]),
+            (LastChange, &["g;"]),
+            (ToggleFollow, &["F"]),
+            (Timeline, &["T"]),
+            (ToggleHooks, &["!"]),
+            (ToggleChecks, &["X"]),
+            (NextCheckRef, &["x"]),
+            (RunCheck, &["R"]),
+        ];
+        let log: Defaults = &[
+            (Back, &["q", "esc"]),
+            (Quit, &["ctrl-c"]),
+            (Open, &["enter"]),
+            (Pager, &["d"]),
+            (HistorySearch, &["S"]),
+            (ToggleFullHash, &["H"]),
+            (ToggleAbsoluteDate, &["D"]),
+            (ToggleRefs, &["R"]),
+            (ToggleGraph, &["T"]),
+            (ToggleAllRefs, &["A"]),
+            (EditFilter, &["F"]),
+        ];
+        let detail: Defaults = &[(Back, &["q", "esc"]), (Quit, &["ctrl-c"]), (Pager, &["d"])];
+        let blame: Defaults = &[
+            (Back, &["q", "esc"]),
+            (Quit, &["ctrl-c"]),
+            (Open, &["enter"]),
+            (BlameParent, &["b"]),
+            (BlameBack, &["B", "backspace"]),
+            (FileLog, &["L"]),
+        ];
+        let timeline: Defaults = &[
+            (Back, &["q", "esc"]),
+            (Quit, &["ctrl-c"]),
+            (Pager, &["d"]),
+            (OlderSnapshot, &["left", ","]),
+            (NewerSnapshot, &["right", "."]),
+            (MarkSnapshot, &["m"]),
+        ];
+        let commit: Defaults = &[
+            (Back, &["q", "esc"]),
+            (Quit, &["ctrl-c"]),
+            (EditMessage, &["e"]),
+            (ToggleAmend, &["a"]),
+            (ToggleSignoff, &["s"]),
+            (ToggleNoVerify, &["n"]),
+            (Commit, &["enter"]),
+        ];
+
+        let screens: [(Screen, Vec<Defaults>); 6] = [
+            (Screen::Diff, vec![general, diff, nav, files, search]),
+            (Screen::CommitLog, vec![general, log, &nav[..4], search]),
+            (
+                Screen::CommitDetail,
+                vec![general, detail, nav, files, search],
+            ),
+            (Screen::Blame, vec![general, blame, nav]),
+            (
+                Screen::Timeline,
+                vec![general, timeline, nav, files, search],
+            ),
+            (Screen::Commit, vec![general, commit]),
+        ];
+
+        let mut bindings = HashMap::new();
+        for (screen, groups) in screens {
+            let mut list = Vec::new();
+            for &(action, keys) in groups.into_iter().flatten() {
+                for key in keys {
+                    let seq = parse_binding(key).expect("default bindings are valid");
+                    list.push((seq, action));
+                }
+            }
+            bindings.insert(screen, list);
+        }
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Default bindings with config overrides applied. Each overridden
+    /// action loses its default keys on that screen.
+    pub fn with_overrides(overrides: &KeyOverrides) -> Result<Self> {
+        let mut map = Self::default();
+        for (screen_name, actions) in overrides {
+            let screen = SCREENS
+                .iter()
+                .find(|(name, _)| name == screen_name)
+                .map(|&(_, s)| s)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = SCREENS.iter().map(|(n, _)| *n).collect();
+                    anyhow!(
+                        "unknown screen `[keys.{screen_name}]` (expected one of: {})",
+                        names.join(", ")
+                    )
+                })?;
+            let list = map.bindings.entry(screen).or_default();
+            for (action_name, keys) in actions {
+                let action = Action::from_name(action_name)
+                    .ok_or_else(|| anyhow!("unknown action `{action_name}`"))?;
+                list.retain(|(_, a)| *a != action);
+                for key in keys {
+                    let seq = parse_binding(key)
+                        .map_err(|e| anyhow!("keys.{screen_name}.{action_name}: {e}"))?;
+                    list.push((seq, action));
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// Match the keys typed so far against `screen`'s bindings.
+    ///
+    /// An exact match wins unless a longer binding shares the prefix, in
+    /// which case the caller should wait for the next key.
+    pub fn resolve(&self, screen: Screen, pending: &[KeyPress]) -> Resolution {
+        let Some(list) = self.bindings.get(&screen) else {
+            return Resolution::NoMatch;
+        };
+        let mut exact = None;
+        let mut longer = false;
+        for (seq, action) in list {
+            if seq.as_slice() == pending {
+                exact.get_or_insert(*action);
+            } else if seq.starts_with(pending) {
+                longer = true;
+            }
+        }
+        match (exact, longer) {
+            (_, true) => Resolution::Pending,
+            (Some(action), false) => Resolution::Action(action),
+            (None, false) => Resolution::NoMatch,
+        }
+    }
+
+    /// Human-readable keys bound to `action` on `screen`, e.g. `["j", "Down"]`.
+    pub fn keys_for(&self, screen: Screen, action: Action) -> Vec<String> {
+        self.bindings
+            .get(&screen)
+            .into_iter()
+            .flatten()
+            .filter(|(_, a)| *a == action)
+            .map(|(seq, _)| seq.iter().map(KeyPress::to_string).collect())
+            .collect()
+    }
+
+    /// Every binding on `screen` grouped by category, for the help overlay.
+    /// `filter` keeps rows whose keys, description or action name contain
+    /// it, ignoring case.
+    pub fn help(&self, screen: Screen, filter: &str) -> Vec<HelpSection> {
+        let filter = filter.to_lowercase();
+        CATEGORIES
+            .into_iter()
+            .map(|category| {
+                let rows = Action::ALL
+                    .into_iter()
+                    .filter(|a| a.category() == category)
+                    .filter_map(|action| {
+                        let keys = self.keys_for(screen, action);
+                        if keys.is_empty() {
+                            return None;
+                        }
+                        let keys = keys.join(", ");
+                        let haystack = format!("{keys} {} {}", action.description(), action.name());
+                        haystack
+                            .to_lowercase()
+                            .contains(&filter)
+                            .then_some((keys, action.description()))
+                    })
+                    .collect();
+                (category, rows)
+            })
+            .filter(|(_, rows): &HelpSection| !rows.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyPress {
+        KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn parse_bindings() {
+        assert_eq!(parse_binding("gg").unwrap(), vec![key('g'), key('g')]);
+        assert_eq!(
+            parse_binding("ctrl-d").unwrap(),
+            vec![KeyPress::new(KeyCode::Char('d'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(
+            parse_binding("pagedown").unwrap(),
+            vec![KeyPress::new(KeyCode::PageDown, KeyModifiers::NONE)]
+        );
+        assert_eq!(parse_binding("g t").unwrap(), vec![key('g'), key('t')]);
+        assert!(parse_binding("ctrl-nope").is_err());
+        assert!(parse_binding("  ").is_err());
+    }
+
+    #[test]
+    fn shift_is_folded_into_characters() {
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyPress::from(event), key('G'));
+    }
+
+    #[test]
+    fn sequences_wait_for_more_keys() {
+        let map = KeyMap::default();
+        assert_eq!(map.resolve(Screen::Diff, &[key('g')]), Resolution::Pending);
+        assert_eq!(
+            map.resolve(Screen::Diff, &[key('g'), key('g')]),
+            Resolution::Action(Action::ScrollTop)
+        );
+        assert_eq!(map.resolve(Screen::Diff, &[key('z')]), Resolution::NoMatch);
+    }
+
+    #[test]
+    fn overrides_replace_default_keys() {
+        let overrides: KeyOverrides =
+            toml::from_str("[diff]\nscroll_down = [\"e\", \"ctrl-e\"]").unwrap();
+        let map = KeyMap::with_overrides(&overrides).unwrap();
+        assert_eq!(
+            map.resolve(Screen::Diff, &[key('e')]),
+            Resolution::Action(Action::ScrollDown)
+        );
+        assert_eq!(map.resolve(Screen::Diff, &[key('j')]), Resolution::NoMatch);
+        assert_eq!(map.keys_for(Screen::Diff, Action::ScrollDown), ["e", "C-e"]);
+    }
+
+    #[test]
+    fn help_lists_bound_actions_by_category() {
+        let map = KeyMap::default();
+        let help = map.help(Screen::Blame, "");
+        let categories: Vec<&str> = help.iter().map(|(c, _)| *c).collect();
+        assert_eq!(categories, ["General", "Navigation", "History"]);
+        assert!(help[1].1.contains(&("C-d".to_string(), "Half page down")));
+
+        let filtered = map.help(Screen::Diff, "fold");
+        let rows: Vec<&str> = filtered.iter().flat_map(|(_, r)| r).map(|r| r.1).collect();
+        assert_eq!(
+            rows,
+            ["Fold / unfold file", "Fold all files", "Unfold all files"]
+        );
+    }
+
+    #[test]
+    fn bad_overrides_are_rejected() {
+        let unknown_action: KeyOverrides = toml::from_str("[diff]\nfly = [\"f\"]").unwrap();
+        assert!(KeyMap::with_overrides(&unknown_action).is_err());
+        let unknown_screen: KeyOverrides = toml::from_str("[moon]\nquit = [\"q\"]").unwrap();
+        assert!(KeyMap::with_overrides(&unknown_screen).is_err());
+    }
+}
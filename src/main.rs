@@ -1,42 +1,141 @@
 mod app;
+mod changes;
+mod check;
+mod clipboard;
+mod command;
+mod config;
+mod control;
 mod diff;
+mod editor;
 mod event;
 mod git;
+mod graph;
+mod history;
+mod hooks;
+mod keymap;
 mod pager;
+mod patch;
+mod pathspec;
+mod process;
+mod report;
+mod search;
+mod theme;
+mod timeline;
+mod tree;
 mod ui;
 mod watcher;
 
-use std::io;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{mpsc, Arc};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::{bail, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use clap::{ArgGroup, Parser};
 use crossterm::{
-    event::{self as ct_event, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{
+        self as ct_event, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use crate::app::{App, DiffView, InputMode, Screen};
+use crate::app::{
+    App, DiffView, Follow, HelpOverlay, InputMode, Pane, Popup, Screen, SearchSource,
+};
+use crate::command::Command;
+use crate::config::{Settings, WatcherMode};
+use crate::control::{Reply, Request};
 use crate::diff::FileDiff;
 use crate::event::AppEvent;
-use crate::git::RepoState;
+use crate::git::{LogFilter, LogSearch, RepoState};
+use crate::history::History;
+use crate::keymap::{Action, KeyPress, Resolution};
+use crate::patch::ExportScope;
+use crate::pathspec::PathFilter;
+use crate::report::{Format, Report};
+
+/// Redraw interval while change highlights fade.
+const FADE_TICK: Duration = Duration::from_secs(1);
 
 #[derive(Parser)]
 #[command(name = "git-monitor", about = "Live Git diff TUI")]
+#[command(group(ArgGroup::new("output").args(["json", "once"])))]
 struct Cli {
     /// Path to the git repository to watch (defaults to cwd)
     #[arg(default_value = ".")]
     repo: PathBuf,
 
-    /// Debounce interval in milliseconds for filesystem events
-    #[arg(long, default_value_t = 200)]
-    debounce_ms: u64,
+    /// Debounce interval in milliseconds for filesystem events [default: 200]
+    #[arg(long)]
+    debounce_ms: Option<u64>,
+
+    /// How to detect filesystem changes [default: native]
+    #[arg(long, value_enum)]
+    watcher: Option<WatcherMode>,
+
+    /// Config file to use instead of ~/.config/git-monitor/config.toml
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Colour theme: dark, light, high-contrast, mono, or a theme from the config
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// Start with mouse capture off, leaving text selection to the terminal
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Only show changes under this path or matching this glob (repeatable)
+    #[arg(long = "path", value_name = "PATHSPEC")]
+    paths: Vec<String>,
+
+    /// Print a JSON object per refresh to stdout instead of starting the TUI
+    #[arg(long)]
+    json: bool,
+
+    /// Print the repository state once and exit: 0 if there is nothing to
+    /// commit, 3 with only staged changes, 4 with unstaged or untracked files
+    #[arg(long)]
+    once: bool,
+
+    /// Output format of --once
+    #[arg(long, value_enum, default_value_t, requires = "once")]
+    format: Format,
+
+    /// Include diff hunks in --json and --once output
+    #[arg(long, requires = "output")]
+    hunks: bool,
+
+    /// Accept commands from editors on a Unix domain socket at this path
+    #[arg(long, value_name = "PATH", conflicts_with = "output")]
+    socket: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Command-line flags take precedence over config files.
+    fn apply(&self, settings: &mut Settings) {
+        if let Some(ms) = self.debounce_ms {
+            settings.debounce_ms = ms;
+        }
+        if let Some(mode) = self.watcher {
+            settings.watcher = mode;
+        }
+        if let Some(theme) = &self.theme {
+            settings.theme_name = theme.clone();
+        }
+        if self.no_mouse {
+            settings.mouse = false;
+        }
+        if !self.paths.is_empty() {
+            settings.paths = self.paths.clone();
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -47,6 +146,25 @@ fn main() -> Result<()> {
         bail!("{} is not a git repository", repo.display());
     }
 
+    // Config files check their own `paths` and report them as config errors
+    PathFilter::new(cli.paths.clone()).context("invalid --path")?;
+    let (mut settings, mut config_errors) = config::load(&repo, cli.config.as_deref());
+    cli.apply(&mut settings);
+    if let Err(e) = settings.resolve_theme() {
+        config_errors.push(format!("{e:#}"));
+    }
+
+    if cli.json || cli.once {
+        for error in &config_errors {
+            eprintln!("git-monitor: {error}");
+        }
+        if cli.once {
+            let code = run_once(&repo, &settings, cli.format, cli.hunks)?;
+            std::process::exit(code);
+        }
+        return run_json(&repo, &settings, cli.hunks);
+    }
+
     // ── Terminal setup ──────────────────────────────────────────
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -58,16 +176,29 @@ fn main() -> Result<()> {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         let _ = disable_raw_mode();
-        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen);
         original_hook(info);
     }));
 
     // ── Run ─────────────────────────────────────────────────────
-    let result = run(&mut terminal, &repo, &cli);
+    let result = run(
+        &mut terminal,
+        &repo,
+        &settings,
+        config_errors,
+        cli.socket.as_deref(),
+    );
+    if settings.timeline_size > 0 {
+        git::git_remove_snapshot_index(&repo);
+    }
 
     // ── Terminal teardown ───────────────────────────────────────
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
 
     result
 }
@@ -75,9 +206,19 @@ fn main() -> Result<()> {
 fn run(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     repo: &Path,
-    cli: &Cli,
+    settings: &Settings,
+    config_errors: Vec<String>,
+    socket: Option<&Path>,
 ) -> Result<()> {
-    let mut app = App::new();
+    let mut app = App::new(settings);
+    app.search_history = History::load(History::default_path("search_history"));
+    app.command_history = History::load(History::default_path("command_history"));
+    if !config_errors.is_empty() {
+        app.popup = Some(Popup {
+            title: " Config errors — using defaults for these files ".into(),
+            body: config_errors.join("\n"),
+        });
+    }
     let (tx, rx) = mpsc::channel::<AppEvent>();
 
     // Shared flag: when true, the keyboard thread stops reading events.
@@ -101,6 +242,11 @@ fn run(
                         break;
                     }
                 }
+                Ok(Event::Mouse(mouse)) => {
+                    if key_tx.send(AppEvent::Mouse(mouse)).is_err() {
+                        break;
+                    }
+                }
                 Ok(Event::Resize(_, _)) => {
                     if key_tx.send(AppEvent::Resize).is_err() {
                         break;
@@ -115,215 +261,1284 @@ fn run(
     });
 
     // ── Filesystem watcher thread ───────────────────────────────
-    let _watcher = watcher::spawn(repo, cli.debounce_ms, tx)?;
+    // The path filter is shared so the prompt can change what the watcher reports
+    let watch_filter = Arc::new(RwLock::new(app.path_filter.clone()));
+    // Hook and check runs report back on the same channel
+    let job_tx = tx.clone();
+    let _control = socket
+        .map(|path| control::listen(path, tx.clone()))
+        .transpose()?;
+    let _watcher = watcher::spawn(repo, settings, Arc::clone(&watch_filter), tx)?;
 
     // ── Initial git query ───────────────────────────────────────
-    let mut state = RepoState::query(repo)
+    let mut state = RepoState::query(repo, app.path_filter.specs(), app.base.as_deref())
         .unwrap_or_else(|_| RepoState::empty("Failed to query git state — is this a valid repo?"));
     app.recompute_visible_lines(current_files(&app, &state));
+    record_snapshot(&mut app, repo, &state);
+    if std::mem::take(&mut app.run_check) {
+        start_check(&mut app, repo, &job_tx);
+    }
 
     // ── Main event loop ─────────────────────────────────────────
+    let mut mouse_captured = false;
+    sync_mouse_capture(terminal, app.mouse, &mut mouse_captured)?;
     terminal.draw(|frame| ui::draw(frame, &mut app, &state))?;
 
-    while let Ok(event) = rx.recv() {
+    loop {
+        // While changes are highlighted, wake up regularly to fade them;
+        // while a check runs, to update its timer
+        let event = if app.changes.is_empty() && app.check.started().is_none() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(FADE_TICK)
+        };
+        let mut fs_changed = false;
+        let mut changed_paths = Vec::new();
         match event {
-            AppEvent::Key(key) => handle_key(&mut app, key, &state, repo),
-            AppEvent::FsChange => {
+            Ok(AppEvent::Key(key)) => handle_key(&mut app, key, &state, repo),
+            Ok(AppEvent::Mouse(mouse)) => handle_mouse(&mut app, mouse, &state, repo),
+            Ok(AppEvent::FsChange(paths)) => {
+                changed_paths = paths;
                 while let Ok(evt) = rx.try_recv() {
                     match evt {
-                        AppEvent::FsChange => {}
+                        // Later batches are newer
+                        AppEvent::FsChange(paths) => {
+                            changed_paths.splice(0..0, paths);
+                        }
                         AppEvent::Key(key) => handle_key(&mut app, key, &state, repo),
+                        AppEvent::Mouse(mouse) => handle_mouse(&mut app, mouse, &state, repo),
+                        AppEvent::HookDone(result) => app.hooks.finish(result, repo, &job_tx),
+                        AppEvent::CheckDone(result) => app.check.finish(result, repo),
+                        AppEvent::Control(request, reply) => {
+                            let _ = reply.send(handle_control(&mut app, request, &state, repo));
+                        }
                         AppEvent::Resize => {}
                     }
                 }
-                state = RepoState::query(repo).unwrap_or(state);
+                app.refresh = true;
+                fs_changed = true;
+            }
+            Ok(AppEvent::HookDone(result)) => app.hooks.finish(result, repo, &job_tx),
+            Ok(AppEvent::CheckDone(result)) => app.check.finish(result, repo),
+            Ok(AppEvent::Control(request, reply)) => {
+                let _ = reply.send(handle_control(&mut app, request, &state, repo));
+            }
+            Ok(AppEvent::Resize) => {}
+            Err(RecvTimeoutError::Timeout) => app.changes.expire(app.change_ttl),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if std::mem::take(&mut app.refresh) {
+            if let Ok(mut filter) = watch_filter.write() {
+                if filter.specs() != app.path_filter.specs() {
+                    *filter = app.path_filter.clone();
+                }
+            }
+            let refreshed_at = Instant::now();
+            if let Ok(new) = RepoState::query(repo, app.path_filter.specs(), app.base.as_deref()) {
+                // Only edits count as changes, not switching filters or base
+                app.track_changes(&state, &new, fs_changed);
+                if fs_changed {
+                    for (hook, files) in app.hooks.triggered(&changed_paths, &state, &new) {
+                        app.hooks.start(hook, files, repo, &job_tx);
+                    }
+                    app.run_check |= app.check.wants(&changed_paths);
+                }
+                state = new;
+                record_snapshot(&mut app, repo, &state);
+            }
+            // The detail and timeline screens show fixed diffs — leave
+            // them untouched
+            if !matches!(app.screen, Screen::CommitDetail | Screen::Timeline) {
                 app.recompute_visible_lines(current_files(&app, &state));
-                if app.search.active {
-                    app.recompute_matches(&app.visible_lines.clone());
+                if !app.search.query.is_empty() {
+                    let sources = search_sources(&app, &state, &[]);
+                    app.recompute_matches(&sources);
                 }
             }
-            AppEvent::Resize => {}
+            if fs_changed && app.screen == Screen::Diff {
+                let files = current_files(&app, &state);
+                app.follow_change(&changed_paths, refreshed_at, files);
+            }
         }
 
-        // ── Pager suspend/restore ───────────────────────────────
+        // ── Pager / editor suspend/restore ──────────────────────
         if let Some(content) = app.pager_content.take() {
-            // Stop the keyboard thread from reading the terminal
-            kbd_paused.store(true, Ordering::Relaxed);
-            // Give it time to finish any in-progress poll/read cycle
-            thread::sleep(Duration::from_millis(150));
-
-            // Leave TUI
-            disable_raw_mode()?;
-            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-
-            let pager_cmd = pager::detect_pager();
-            let _ = pager::open_pager(&content, &pager_cmd);
-
-            // Re-enter TUI
-            enable_raw_mode()?;
-            execute!(terminal.backend_mut(), EnterAlternateScreen)?;
-            terminal.clear()?;
+            suspend_tui(terminal, &kbd_paused, &job_tx, &rx, mouse_captured, || {
+                let pager_cmd = settings.pager.clone().unwrap_or_else(pager::detect_pager);
+                let _ = pager::open_pager(&content, &pager_cmd);
+            })?;
+        }
+        if let Some(path) = app.editor_request.take() {
+            let mut result = Ok(());
+            suspend_tui(terminal, &kbd_paused, &job_tx, &rx, mouse_captured, || {
+                let editor_cmd = editor::detect_editor(repo);
+                result = editor::open_editor(&path, &editor_cmd);
+            })?;
+            match result.and_then(|_| std::fs::read_to_string(&path)) {
+                Ok(text) => app.set_commit_message(&text),
+                Err(e) => app.message = Some(format!("editor failed: {e}")),
+            }
+        }
 
-            // Drain any events queued while the pager was active
-            while rx.try_recv().is_ok() {}
+        if let Some(text) = app.clipboard.take() {
+            if let Err(e) = clipboard::copy(terminal.backend_mut(), &text) {
+                app.message = Some(format!("copy failed: {e}"));
+            }
+        }
 
-            // Resume the keyboard thread
-            kbd_paused.store(false, Ordering::Relaxed);
+        if std::mem::take(&mut app.run_check) {
+            start_check(&mut app, repo, &job_tx);
         }
 
         if app.should_quit {
             break;
         }
 
+        sync_mouse_capture(terminal, app.mouse, &mut mouse_captured)?;
         terminal.draw(|frame| ui::draw(frame, &mut app, &state))?;
     }
 
+    app.check.cancel();
+    app.hooks.cancel();
+    Ok(())
+}
+
+/// Headless mode: write a JSON report of the repository to stdout after
+/// every refresh, one object per line, until the reader goes away.
+fn run_json(repo: &Path, settings: &Settings, hunks: bool) -> Result<()> {
+    let filter = PathFilter::new(settings.paths.clone())?;
+    let specs = filter.specs().to_vec();
+    let (tx, rx) = mpsc::channel::<AppEvent>();
+    let _watcher = watcher::spawn(repo, settings, Arc::new(RwLock::new(filter)), tx)?;
+
+    let mut out = io::stdout().lock();
+    let mut changed = Vec::new();
+    loop {
+        match RepoState::query(repo, &specs, None) {
+            Ok(state) => {
+                let line = Report::new(&state, &changed, hunks).to_json();
+                match writeln!(out, "{line}").and_then(|_| out.flush()) {
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                    result => result?,
+                }
+            }
+            // Git can fail transiently, e.g. while another process holds
+            // the index lock
+            Err(e) => eprintln!("git-monitor: {e:#}"),
+        }
+        let Ok(AppEvent::FsChange(paths)) = rx.recv() else {
+            break;
+        };
+        changed = paths;
+        while let Ok(AppEvent::FsChange(paths)) = rx.try_recv() {
+            // Later batches are newer
+            changed.splice(0..0, paths);
+        }
+    }
+    Ok(())
+}
+
+/// Print the repository state in `format` and return the exit status it
+/// calls for.
+fn run_once(repo: &Path, settings: &Settings, format: Format, hunks: bool) -> Result<i32> {
+    let filter = PathFilter::new(settings.paths.clone())?;
+    let state = RepoState::query(repo, filter.specs(), None)?;
+    let report = Report::new(&state, &[], hunks);
+    let color = io::stdout().is_terminal() && !theme::no_color();
+    let mut out = io::stdout().lock();
+    out.write_all(report.render(format, color).as_bytes())?;
+    out.flush()?;
+    Ok(report.exit_code())
+}
+
+/// Start a check run, cancelling the one in progress.
+fn start_check(app: &mut App, repo: &Path, tx: &Sender<AppEvent>) {
+    if let Err(e) = app.check.start(repo, tx) {
+        app.message = Some(format!("{}: {e:#}", app.check.name()));
+    }
+}
+
+/// Turn terminal mouse capture on or off to match `wanted`.
+fn sync_mouse_capture(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    wanted: bool,
+    captured: &mut bool,
+) -> Result<()> {
+    if wanted != *captured {
+        if wanted {
+            execute!(terminal.backend_mut(), EnableMouseCapture)?;
+        } else {
+            execute!(terminal.backend_mut(), DisableMouseCapture)?;
+        }
+        *captured = wanted;
+    }
+    Ok(())
+}
+
+/// Leave the TUI, run `f` with the terminal handed to a child process,
+/// then restore the TUI. Events other than input that arrived meanwhile
+/// are sent again on `tx`.
+fn suspend_tui(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    kbd_paused: &AtomicBool,
+    tx: &Sender<AppEvent>,
+    rx: &mpsc::Receiver<AppEvent>,
+    mouse: bool,
+    f: impl FnOnce(),
+) -> Result<()> {
+    // Stop the keyboard thread from reading the terminal
+    kbd_paused.store(true, Ordering::Relaxed);
+    // Give it time to finish any in-progress poll/read cycle
+    thread::sleep(Duration::from_millis(150));
+
+    // Leave TUI
+    disable_raw_mode()?;
+    if mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    f();
+
+    // Re-enter TUI
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    if mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
+    terminal.clear()?;
+
+    // Drop input queued while the child process was active; hook and
+    // check results and control requests go back on the queue
+    for event in event::drain_input(rx) {
+        let _ = tx.send(event);
+    }
+
+    // Resume the keyboard thread
+    kbd_paused.store(false, Ordering::Relaxed);
     Ok(())
 }
 
 /// Return the structured file diffs for the current view.
 fn current_files<'a>(app: &App, state: &'a RepoState) -> &'a [FileDiff] {
-    match app.view {
+    view_files(app.view, state)
+}
+
+fn view_files(view: DiffView, state: &RepoState) -> &[FileDiff] {
+    match view {
         DiffView::Unstaged => &state.unstaged_diff,
         DiffView::Staged => &state.staged_diff,
     }
 }
 
+/// Diffs the search runs over: the current view, then the other one when
+/// searching both. The detail and timeline screens search their `detail`
+/// files, and the commit log none, as it searches its commits.
+fn search_sources<'a>(
+    app: &App,
+    state: &'a RepoState,
+    detail: &'a [FileDiff],
+) -> Vec<SearchSource<'a>> {
+    match app.screen {
+        Screen::CommitDetail | Screen::Timeline => return vec![(app.view, detail)],
+        Screen::CommitLog => return Vec::new(),
+        _ => {}
+    }
+    let mut sources = vec![(app.view, current_files(app, state))];
+    if app.search.all_views && app.screen == Screen::Diff {
+        let other = app.view.other();
+        sources.push((other, view_files(other, state)));
+    }
+    sources
+}
+
+/// Load the first page of the commit log for `app.log_query`.
+///
+/// Returns `false` if git failed, leaving the current log untouched.
+fn load_commit_log(app: &mut App, repo: &Path) -> bool {
+    app.log_query.skip = 0;
+    match git::git_log(repo, &app.log_query) {
+        Ok(page) => {
+            app.reset_commit_log(page);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Switch to the commit log screen with a freshly loaded first page.
+fn open_commit_log(app: &mut App, repo: &Path) {
+    if load_commit_log(app, repo) {
+        app.screen = Screen::CommitLog;
+        app.clear_search();
+    }
+}
+
+/// Fetch the next commit log page when the cursor nears the end.
+fn load_more_commits(app: &mut App, repo: &Path) {
+    if app.commit_log_wants_more() {
+        match git::git_log(repo, &app.log_query) {
+            Ok(page) => app.append_commit_log(page),
+            Err(_) => app.commit_log_exhausted = true,
+        }
+    }
+}
+
 /// Dispatch a single key event based on current input mode and screen.
 fn handle_key(app: &mut App, key: KeyEvent, state: &RepoState, repo: &Path) {
+    app.message = None;
+    // Any key dismisses a popup
+    if app.popup.take().is_some() {
+        return;
+    }
+    if app.help.is_some() {
+        handle_help_key(app, key);
+        return;
+    }
     match app.input_mode {
-        InputMode::Search => handle_search_input(app, key),
-        InputMode::Normal => match app.screen {
-            Screen::Diff => handle_diff_key(app, key, state, repo),
-            Screen::CommitLog => handle_commit_log_key(app, key, repo),
-        },
+        InputMode::Search => handle_search_input(app, key, state),
+        InputMode::HistorySearch => handle_history_search_input(app, key, repo),
+        InputMode::LogFilter => handle_log_filter_input(app, key, repo),
+        InputMode::PathFilter => handle_path_filter_input(app, key),
+        InputMode::Command => handle_command_input(app, key, state, repo),
+        InputMode::Normal => {
+            let Some((action, count)) = app.feed_key(KeyPress::from(key)) else {
+                return;
+            };
+            for _ in 0..count {
+                handle_action(app, action, state, repo);
+            }
+        }
+    }
+}
+
+/// Run a bound action on the current screen.
+fn handle_action(app: &mut App, action: Action, state: &RepoState, repo: &Path) {
+    match action {
+        Action::Help => {
+            app.help = Some(HelpOverlay::default());
+            return;
+        }
+        Action::ToggleMouse => {
+            app.mouse = !app.mouse;
+            app.message = Some(format!(
+                "mouse {}",
+                if app.mouse {
+                    "on"
+                } else {
+                    "off — terminal selection works"
+                }
+            ));
+            return;
+        }
+        Action::CommandPrompt => {
+            app.enter_command();
+            return;
+        }
+        _ => {}
+    }
+    match app.screen {
+        Screen::Diff => handle_diff_action(app, action, state, repo),
+        Screen::CommitLog => handle_commit_log_action(app, action, repo),
+        Screen::CommitDetail => handle_commit_detail_action(app, action, state, repo),
+        Screen::Blame => handle_blame_action(app, action, repo),
+        Screen::Timeline => handle_timeline_action(app, action, state, repo),
+        Screen::Commit => handle_commit_action(app, action, state, repo),
     }
 }
 
 // ── Search input mode ───────────────────────────────────────────
 
-fn handle_search_input(app: &mut App, key: KeyEvent) {
+fn handle_search_input(app: &mut App, key: KeyEvent, state: &RepoState) {
+    app.with_detail_files(|app, detail| {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('a') {
+            // Sources depend on the setting, so flip it before collecting them
+            let sources = search_sources(app, state, detail);
+            app.search_toggle_all_views(&sources);
+        }
+        let sources = search_sources(app, state, detail);
+        match key.code {
+            KeyCode::Esc => app.search_cancel(&sources),
+            KeyCode::Enter => app.search_confirm(&sources),
+            KeyCode::Tab => app.search_cycle_scope(&sources),
+            KeyCode::Up => app.search_browse(true, &sources),
+            KeyCode::Down => app.search_browse(false, &sources),
+            KeyCode::Char('r') if ctrl => app.search_toggle_literal(&sources),
+            KeyCode::Char('a') if ctrl => app.search_update(&sources),
+            KeyCode::Backspace => app.search_pop(&sources),
+            KeyCode::Char(c) => app.search_push(c, &sources),
+            _ => {}
+        }
+    });
+}
+
+// ── Mouse ───────────────────────────────────────────────────────
+
+/// Lines moved per wheel notch.
+const WHEEL_LINES: usize = 3;
+
+fn handle_mouse(app: &mut App, mouse: MouseEvent, state: &RepoState, repo: &Path) {
+    let (column, row) = (mouse.column, mouse.row);
+    let click = mouse.kind == MouseEventKind::Down(MouseButton::Left);
+    if app.popup.is_some() {
+        if click {
+            app.popup = None;
+        }
+        return;
+    }
+    if let Some(help) = app.help.as_mut() {
+        match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                help.scroll = help.scroll.saturating_add(WHEEL_LINES as u16)
+            }
+            MouseEventKind::ScrollUp => {
+                help.scroll = help.scroll.saturating_sub(WHEEL_LINES as u16)
+            }
+            _ => {}
+        }
+        return;
+    }
+    if app.input_mode != InputMode::Normal {
+        return;
+    }
+
+    match mouse.kind {
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let action = if mouse.kind == MouseEventKind::ScrollDown {
+                Action::ScrollDown
+            } else {
+                Action::ScrollUp
+            };
+            for _ in 0..WHEEL_LINES {
+                handle_action(app, action, state, repo);
+            }
+        }
+        _ if click => {
+            app.message = None;
+            app.clear_pending_keys();
+            if let Some(action) = app.click_map.button_at(column, row) {
+                handle_action(app, action, state, repo);
+            } else if let Some(item) = app.click_map.sidebar_item_at(column, row) {
+                handle_tree_click(app, item, state);
+            } else if let Some(item) = app.click_map.item_at(column, row) {
+                handle_click(app, item, state, repo);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A left click on row `item` of the file tree: folds a directory or
+/// jumps to a file, and focuses the tree.
+fn handle_tree_click(app: &mut App, item: usize, state: &RepoState) {
+    let rows = app.file_tree(&state.files, current_files(app, state));
+    let Some(row) = rows.get(item) else {
+        return;
+    };
+    app.focus = Pane::Sidebar;
+    app.tree_selected = item;
+    if !app.toggle_tree_dir(row) {
+        app.tree_move(0, &rows);
+    }
+}
+
+/// A left click on line or item `item` of the current screen's content.
+fn handle_click(app: &mut App, item: usize, state: &RepoState, repo: &Path) {
+    let double = app.register_click(item);
+    match app.screen {
+        Screen::Diff => {
+            app.focus = Pane::Diff;
+            app.toggle_fold_at(item, current_files(app, state));
+        }
+        Screen::CommitDetail | Screen::Timeline => {
+            app.with_detail_files(|app, files| app.toggle_fold_at(item, files));
+        }
+        Screen::CommitLog if item < app.commit_log.len() => {
+            app.commit_log_selected = item;
+            load_more_commits(app, repo);
+            if double {
+                handle_action(app, Action::Open, state, repo);
+            }
+        }
+        Screen::Blame => {
+            let len = app.blame_stack.last().map_or(0, |v| v.lines.len());
+            if item < len {
+                let selected = app.blame_stack.last().map_or(0, |v| v.selected);
+                app.blame_move(item as isize - selected as isize);
+                if double {
+                    handle_action(app, Action::Open, state, repo);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// ── Help overlay ────────────────────────────────────────────────
+
+fn handle_help_key(app: &mut App, key: KeyEvent) {
+    let page = app.viewport_height.max(2);
+    let is_help_key =
+        app.keymap.resolve(app.screen, &[KeyPress::from(key)]) == Resolution::Action(Action::Help);
+    let Some(help) = app.help.as_mut() else {
+        return;
+    };
+    if help.editing {
+        match key.code {
+            KeyCode::Esc => {
+                help.editing = false;
+                help.filter.clear();
+            }
+            KeyCode::Enter => help.editing = false,
+            KeyCode::Backspace => {
+                help.filter.pop();
+            }
+            KeyCode::Char(c) => {
+                help.filter.push(c);
+                help.scroll = 0;
+            }
+            _ => {}
+        }
+        return;
+    }
+    match (key.code, key.modifiers) {
+        (KeyCode::Esc, _) if !help.filter.is_empty() => help.filter.clear(),
+        (KeyCode::Char('q') | KeyCode::Esc, _) => app.help = None,
+        _ if is_help_key => app.help = None,
+        (KeyCode::Char('c'), KeyModifiers::CONTROL) => app.should_quit = true,
+        (KeyCode::Char('/'), _) => help.editing = true,
+        (KeyCode::Char('j') | KeyCode::Down, _) => help.scroll = help.scroll.saturating_add(1),
+        (KeyCode::Char('k') | KeyCode::Up, _) => help.scroll = help.scroll.saturating_sub(1),
+        (KeyCode::Char('d'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
+            help.scroll = help.scroll.saturating_add(page / 2)
+        }
+        (KeyCode::Char('u'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+            help.scroll = help.scroll.saturating_sub(page / 2)
+        }
+        (KeyCode::Char('g') | KeyCode::Home, _) => help.scroll = 0,
+        // Clamped to the content when drawn
+        (KeyCode::Char('G') | KeyCode::End, _) => help.scroll = u16::MAX,
+        _ => {}
+    }
+}
+
+// ── History search input mode ───────────────────────────────────
+
+fn handle_history_search_input(app: &mut App, key: KeyEvent, repo: &Path) {
     match key.code {
-        KeyCode::Esc => app.clear_search(),
+        KeyCode::Esc => app.input_mode = InputMode::Normal,
         KeyCode::Enter => {
-            let lines = app.visible_lines.clone();
-            app.search_confirm(&lines);
+            app.input_mode = InputMode::Normal;
+            app.log_query.search = LogSearch::parse(&app.history_query);
+            load_commit_log(app, repo);
+        }
+        KeyCode::Backspace => {
+            app.history_query.pop();
         }
-        KeyCode::Backspace => app.search_pop(),
-        KeyCode::Char(c) => app.search_push(c),
+        KeyCode::Char(c) => app.history_query.push(c),
         _ => {}
     }
 }
 
-// ── Normal mode — Diff screen ───────────────────────────────────
+// ── Log filter input mode ───────────────────────────────────────
 
-fn handle_diff_key(app: &mut App, key: KeyEvent, state: &RepoState, repo: &Path) {
-    match (key.code, key.modifiers) {
-        // Quit
-        (KeyCode::Char('q'), _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-            app.should_quit = true;
+fn handle_log_filter_input(app: &mut App, key: KeyEvent, repo: &Path) {
+    match key.code {
+        KeyCode::Esc => app.input_mode = InputMode::Normal,
+        KeyCode::Enter => match LogFilter::parse(&app.log_filter_input) {
+            Ok(filter) => {
+                app.input_mode = InputMode::Normal;
+                app.log_query.filter = filter;
+                if !load_commit_log(app, repo) {
+                    app.message = Some("git log failed — check the filter values".into());
+                }
+            }
+            // Stay in the prompt so the input can be fixed
+            Err(e) => app.message = Some(e.to_string()),
+        },
+        KeyCode::Backspace => {
+            app.log_filter_input.pop();
         }
-        // View toggle
-        (KeyCode::Tab, _) => {
-            app.toggle_view();
+        KeyCode::Char(c) => app.log_filter_input.push(c),
+        _ => {}
+    }
+}
+
+// ── Command input mode ──────────────────────────────────────────
+
+fn handle_command_input(app: &mut App, key: KeyEvent, state: &RepoState, repo: &Path) {
+    match key.code {
+        KeyCode::Esc => app.input_mode = InputMode::Normal,
+        KeyCode::Enter => {
+            let input = app.command_finish();
+            match Command::parse(&input) {
+                Ok(command) => run_command(app, command, state, repo),
+                Err(e) => app.message = Some(e.to_string()),
+            }
+        }
+        KeyCode::Tab => {
+            let refs = git::git_refs(repo).unwrap_or_default();
+            app.command_complete(&refs, repo);
+        }
+        KeyCode::Up => app.command_browse(true),
+        KeyCode::Down => app.command_browse(false),
+        KeyCode::Backspace => app.command_pop(),
+        KeyCode::Char(c) => app.command_push(c),
+        _ => {}
+    }
+}
+
+fn run_command(app: &mut App, command: Command, state: &RepoState, repo: &Path) {
+    match command {
+        Command::Base(Some(rev)) => match git::git_verify_commit(repo, &rev) {
+            Ok(()) => {
+                app.message = Some(format!("comparing against {rev}"));
+                app.base = Some(rev);
+                app.scroll = 0;
+                app.refresh = true;
+            }
+            Err(e) => app.message = Some(e.to_string()),
+        },
+        Command::Base(None) => {
+            app.base = None;
+            app.scroll = 0;
+            app.refresh = true;
+        }
+        Command::Filter(filter) => {
+            app.path_filter = filter;
+            app.scroll = 0;
+            app.refresh = true;
+        }
+        Command::Log(filter) => {
+            app.log_query.filter = filter;
+            open_commit_log(app, repo);
+        }
+        Command::Write(scope, path, force) => {
+            let patch = export_patch(app, scope, state);
+            let lines = patch.lines().count();
+            app.message = Some(if patch.is_empty() {
+                "nothing to write".into()
+            } else {
+                match patch::write(&repo.join(&path), &patch, force) {
+                    Ok(()) => format!("wrote {lines} lines to {}", path.display()),
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                        format!("{} exists (:w! to overwrite)", path.display())
+                    }
+                    Err(e) => format!("{}: {e}", path.display()),
+                }
+            });
+        }
+        Command::Yank(scope) => {
+            let patch = export_patch(app, scope, state);
+            copy_patch(app, patch);
+        }
+        Command::Quit => app.should_quit = true,
+    }
+}
+
+// ── Control socket ──────────────────────────────────────────────
+
+/// Carry out a request from the control socket.
+fn handle_control(app: &mut App, request: Request, state: &RepoState, repo: &Path) -> Reply {
+    match request {
+        Request::View(view) => {
+            app.show_diff();
+            if app.view != view {
+                app.toggle_view();
+            }
             app.recompute_visible_lines(current_files(app, state));
         }
-        // Basic scroll
-        (KeyCode::Char('j') | KeyCode::Down, _) => app.scroll_down(1),
-        (KeyCode::Char('k') | KeyCode::Up, _) => app.scroll_up(1),
-        (KeyCode::Char('g'), _) => app.scroll_to_top(),
-        (KeyCode::Char('G'), _) => app.scroll_to_bottom(),
-        // Half-page scroll
-        (KeyCode::Char('d'), KeyModifiers::CONTROL) => app.scroll_half_down(),
-        (KeyCode::Char('u'), KeyModifiers::CONTROL) => app.scroll_half_up(),
-        // Full-page scroll
-        (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
-            app.scroll_down(app.viewport_height)
-        }
-        (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
-            app.scroll_up(app.viewport_height)
-        }
-        // File navigation
-        (KeyCode::Char(']'), _) => app.next_file(),
-        (KeyCode::Char('['), _) => app.prev_file(),
-        // File fold toggle
-        (KeyCode::Char(' '), _) => {
-            let files = current_files(app, state);
-            app.toggle_file_fold(files);
+        Request::Command(command) => {
+            if let Command::Base(Some(rev)) = &command {
+                git::git_verify_commit(repo, rev).map_err(|e| e.to_string())?;
+            }
+            run_command(app, command, state, repo);
         }
-        // Collapse / expand all
-        (KeyCode::Char('C'), _) => {
+        Request::Jump(path, line) => {
+            let path = control::relative(&path, repo);
+            // Prefer the view on screen when the file is in both
+            let view = [app.view, app.view.other()]
+                .into_iter()
+                .find(|&v| view_files(v, state).iter().any(|fd| fd.filename == path))
+                .ok_or_else(|| format!("{path} is not in the diff"))?;
+            app.show_diff();
+            if app.view != view {
+                app.toggle_view();
+            }
             let files = current_files(app, state);
-            app.fold_all(files);
+            app.recompute_visible_lines(files);
+            // Files without hunks, such as binary files, only have a header
+            if !line.is_some_and(|line| app.jump_to_line(&path, line, files)) {
+                app.pause_follow();
+                app.jump_to_file(&path);
+            }
         }
-        (KeyCode::Char('E'), _) => {
+        Request::Refresh => app.refresh = true,
+        Request::State { hunks } => {
+            let report = Report::new(state, &[], hunks);
+            return Ok(Some(
+                serde_json::to_value(report).map_err(|e| e.to_string())?,
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// Patch of `scope` of the diff on screen, by default the visual selection
+/// or else the whole view.
+fn export_patch(app: &App, scope: Option<ExportScope>, state: &RepoState) -> String {
+    let files = match app.screen {
+        Screen::CommitDetail | Screen::Timeline => app.detail_files(),
+        _ => current_files(app, state),
+    };
+    let scope = scope.unwrap_or_else(|| app.default_export_scope());
+    patch::build(&app.export_parts(scope, files))
+}
+
+/// Hand `patch` to the clipboard, ending the visual selection.
+fn copy_patch(app: &mut App, patch: String) {
+    if patch.is_empty() {
+        app.message = Some("nothing to copy".into());
+        return;
+    }
+    app.message = Some(format!("copied {} lines", patch.lines().count()));
+    app.clipboard = Some(patch);
+    app.selection = None;
+}
+
+// ── Path filter input mode ──────────────────────────────────────
+
+fn handle_path_filter_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.input_mode = InputMode::Normal,
+        KeyCode::Enter => match PathFilter::parse(&app.path_filter_input) {
+            Ok(filter) => {
+                app.input_mode = InputMode::Normal;
+                app.path_filter = filter;
+                app.scroll = 0;
+                app.refresh = true;
+            }
+            // Stay in the prompt so the input can be fixed
+            Err(e) => app.message = Some(e.to_string()),
+        },
+        KeyCode::Backspace => {
+            app.path_filter_input.pop();
+        }
+        KeyCode::Char(c) => app.path_filter_input.push(c),
+        _ => {}
+    }
+}
+
+// ── Normal mode — Diff screen ───────────────────────────────────
+
+fn handle_diff_action(app: &mut App, action: Action, state: &RepoState, repo: &Path) {
+    // Moving around by hand pauses follow mode
+    let scroll = app.scroll;
+    if app.sidebar && app.focus == Pane::Sidebar && handle_sidebar_action(app, action, state) {
+        if app.scroll != scroll {
+            app.pause_follow();
+        }
+        return;
+    }
+    match action {
+        Action::Quit => app.should_quit = true,
+        Action::ToggleSidebar => app.toggle_sidebar(),
+        Action::EditPathFilter => app.enter_path_filter(),
+        Action::FocusPane => {
+            if !app.sidebar {
+                app.toggle_sidebar();
+            } else if app.focus == Pane::Sidebar {
+                app.focus = Pane::Diff;
+            } else {
+                app.focus = Pane::Sidebar;
+            }
+        }
+        Action::LastChange => {
             let files = current_files(app, state);
-            app.unfold_all(files);
-        }
-        // Search
-        (KeyCode::Char('/'), _) => app.enter_search(true),
-        (KeyCode::Char('?'), _) => app.enter_search(false),
-        (KeyCode::Char('n'), _) => app.search_next(),
-        (KeyCode::Char('N'), _) => app.search_prev(),
-        (KeyCode::Esc, _) => app.clear_search(),
-        // Pager — sends visible (expanded) lines
-        (KeyCode::Char('d'), KeyModifiers::NONE) => {
-            let content: String = app
-                .visible_lines
-                .iter()
-                .map(|l| format!("{}\n", l.text()))
-                .collect();
-            if !content.trim().is_empty() {
-                app.pager_content = Some(content);
-            }
-        }
-        // Commit log
-        (KeyCode::Char('l'), _) => {
-            if let Ok(log) = git::git_log(repo, 50) {
-                app.commit_log = log;
-                app.commit_log_selected = 0;
-                app.screen = Screen::CommitLog;
-                app.clear_search();
+            if !app.jump_to_latest_change(files) {
+                app.message = Some("no recent changes".into());
             }
         }
+        Action::ToggleView => {
+            app.toggle_view();
+            app.recompute_visible_lines(current_files(app, state));
+        }
+        // Pager — the selection or the whole view as a patch
+        Action::Pager => {
+            let patch = export_patch(app, None, state);
+            if !patch.is_empty() {
+                app.pager_content = Some(patch);
+            }
+        }
+        Action::CommitLog => {
+            app.log_query.filter.path = None;
+            open_commit_log(app, repo);
+        }
+        Action::Timeline if !app.timeline.enabled() => {
+            app.message = Some("timeline is off (timeline_size = 0)".into());
+        }
+        Action::ToggleChecks | Action::RunCheck if !app.check.enabled() => {
+            app.message = Some("no check configured".into());
+        }
+        Action::ToggleChecks => app.check_panel = !app.check_panel,
+        Action::RunCheck => app.run_check = true,
+        Action::NextCheckRef => match app.check.next_ref().cloned() {
+            Some(r) => {
+                let found = app.jump_to_line(&r.path, r.line, current_files(app, state));
+                app.check_panel = true;
+                app.message = Some(if found {
+                    format!("{}:{}", r.path, r.line)
+                } else {
+                    format!("{}:{} is not in this diff", r.path, r.line)
+                });
+            }
+            None => app.message = Some("no file references in the check output".into()),
+        },
+        Action::ToggleHooks => {
+            if app.hooks.is_empty() {
+                app.message = Some("no hooks configured".into());
+            } else {
+                app.hook_panel = !app.hook_panel;
+            }
+        }
+        Action::Timeline => {
+            if app.open_timeline() {
+                load_timeline_diff(app, repo);
+            } else {
+                app.message = Some("no snapshots yet".into());
+            }
+        }
+        Action::ComposeCommit => match git::git_path(repo, "COMMIT_EDITMSG") {
+            Ok(path) => app.open_commit_composer(path),
+            Err(e) => app.message = Some(e.to_string()),
+        },
+        // Blame the file under the cursor
+        Action::Blame => {
+            if let Some(path) = app.file_at_scroll() {
+                match git::git_blame(repo, &path, None) {
+                    Ok(lines) => app.push_blame(path, None, lines),
+                    Err(_) => app.message = Some(format!("git blame failed for {path}")),
+                }
+            }
+        }
+        // Commit log scoped to the file under the cursor
+        Action::FileLog => {
+            if let Some(path) = app.file_at_scroll() {
+                app.log_query.filter.path = Some(path);
+                open_commit_log(app, repo);
+            }
+        }
+        Action::ToggleFollow => {
+            app.toggle_follow(current_files(app, state));
+            app.message = Some(match app.follow {
+                Follow::On => "following changes".into(),
+                _ => "follow off".into(),
+            });
+        }
+        _ => {
+            let sources = search_sources(app, state, &[]);
+            handle_diff_nav_action(app, action, current_files(app, state), &sources);
+            if app.scroll != scroll {
+                app.pause_follow();
+            }
+        }
+    }
+}
+
+/// Navigation inside the file tree. Returns `false` for actions the tree
+/// doesn't handle, which then apply to the diff as usual.
+fn handle_sidebar_action(app: &mut App, action: Action, state: &RepoState) -> bool {
+    let files = current_files(app, state);
+    let rows = app.file_tree(&state.files, files);
+    let page = app.viewport_height.max(1) as isize;
+    match action {
+        Action::ScrollDown => app.tree_move(1, &rows),
+        Action::ScrollUp => app.tree_move(-1, &rows),
+        Action::ScrollTop => app.tree_move(isize::MIN, &rows),
+        Action::ScrollBottom => app.tree_move(isize::MAX, &rows),
+        Action::HalfPageDown => app.tree_move(page / 2, &rows),
+        Action::HalfPageUp => app.tree_move(-page / 2, &rows),
+        Action::PageDown => app.tree_move(page, &rows),
+        Action::PageUp => app.tree_move(-page, &rows),
+        // Directories fold; on a file, Enter moves to the diff and Space
+        // folds the file's diff section
+        Action::Open | Action::ToggleFold => {
+            let Some(row) = rows.get(app.tree_selected) else {
+                return true;
+            };
+            if app.toggle_tree_dir(row) {
+                return true;
+            }
+            if let Some(path) = row.file_path() {
+                app.jump_to_file(path);
+                if action == Action::Open {
+                    app.focus = Pane::Diff;
+                } else {
+                    app.toggle_file_fold(files);
+                }
+            }
+        }
+        Action::ClearSearch if !app.search.active => app.focus = Pane::Diff,
+        _ => return false,
+    }
+    true
+}
+
+/// Scroll, file navigation, folding and search actions shared by every
+/// screen that renders `visible_lines`.
+fn handle_diff_nav_action(
+    app: &mut App,
+    action: Action,
+    files: &[FileDiff],
+    sources: &[SearchSource],
+) {
+    // While selecting, motions move the selection's cursor
+    if app.selection.is_some() {
+        let page = app.viewport_height.max(1) as isize;
+        let delta = match action {
+            Action::ScrollDown => 1,
+            Action::ScrollUp => -1,
+            Action::HalfPageDown => page / 2,
+            Action::HalfPageUp => -page / 2,
+            Action::PageDown => page,
+            Action::PageUp => -page,
+            Action::ScrollTop => isize::MIN,
+            Action::ScrollBottom => isize::MAX,
+            _ => 0,
+        };
+        if delta != 0 {
+            app.move_selection(delta);
+            return;
+        }
+    }
+    match action {
+        Action::ScrollDown => app.scroll_down(1),
+        Action::ScrollUp => app.scroll_up(1),
+        Action::ScrollTop => app.scroll_to_top(),
+        Action::ScrollBottom => app.scroll_to_bottom(),
+        Action::HalfPageDown => app.scroll_half_down(),
+        Action::HalfPageUp => app.scroll_half_up(),
+        Action::PageDown => app.scroll_down(app.viewport_height),
+        Action::PageUp => app.scroll_up(app.viewport_height),
+        Action::NextFile => app.next_file(),
+        Action::PrevFile => app.prev_file(),
+        Action::ToggleFold => app.toggle_file_fold(files),
+        Action::FoldAll => app.fold_all(files),
+        Action::UnfoldAll => app.unfold_all(files),
+        Action::VisualSelect => app.toggle_selection(),
+        Action::Yank => {
+            let scope = match app.selection {
+                Some(_) => ExportScope::Selection,
+                None => ExportScope::Hunk,
+            };
+            copy_patch(app, patch::build(&app.export_parts(scope, files)));
+        }
+        Action::YankFile => {
+            copy_patch(
+                app,
+                patch::build(&app.export_parts(ExportScope::File, files)),
+            );
+        }
+        Action::SearchForward => app.enter_search(true),
+        Action::SearchBackward => app.enter_search(false),
+        Action::SearchNext => app.search_next(sources),
+        Action::SearchPrev => app.search_prev(sources),
+        Action::ClearSearch if app.selection.is_some() => app.selection = None,
+        Action::ClearSearch => app.clear_search(),
         _ => {}
     }
 }
 
 // ── Normal mode — Commit Log screen ─────────────────────────────
 
-fn handle_commit_log_key(app: &mut App, key: KeyEvent, repo: &Path) {
-    match (key.code, key.modifiers) {
-        // Back to diff
-        (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => {
+fn handle_commit_log_action(app: &mut App, action: Action, repo: &Path) {
+    match action {
+        // Drop an active history search before leaving the log
+        Action::Back if app.log_query.search.is_some() => {
+            app.log_query.search = None;
+            load_commit_log(app, repo);
+        }
+        Action::Back => {
             app.screen = Screen::Diff;
             app.clear_search();
         }
-        (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-            app.should_quit = true;
-        }
+        Action::Quit => app.should_quit = true,
         // Navigate
-        (KeyCode::Char('j') | KeyCode::Down, _) => app.commit_log_down(),
-        (KeyCode::Char('k') | KeyCode::Up, _) => app.commit_log_up(),
-        (KeyCode::Char('g'), _) => app.commit_log_selected = 0,
-        (KeyCode::Char('G'), _) => {
-            if !app.commit_log.is_empty() {
-                app.commit_log_selected = app.commit_log.len() - 1;
+        Action::ScrollDown => {
+            app.commit_log_down();
+            load_more_commits(app, repo);
+        }
+        Action::ScrollUp => app.commit_log_up(),
+        Action::ScrollTop => app.commit_log_selected = 0,
+        Action::ScrollBottom if !app.commit_log.is_empty() => {
+            app.commit_log_selected = app.commit_log.len() - 1;
+            load_more_commits(app, repo);
+        }
+        // Open commit in the detail screen
+        Action::Open => {
+            if let Some(entry) = app.commit_log.get(app.commit_log_selected) {
+                if let Ok(detail) = git::git_commit_detail(repo, &entry.hash) {
+                    app.open_commit_detail(detail);
+                }
             }
         }
         // View commit in external pager
-        (KeyCode::Enter, _) | (KeyCode::Char('d'), KeyModifiers::NONE) => {
+        Action::Pager => {
             if let Some(entry) = app.commit_log.get(app.commit_log_selected) {
                 if let Ok(raw) = git::git_show(repo, &entry.hash) {
                     app.pager_content = Some(raw);
                 }
             }
         }
+        // Optional columns
+        Action::ToggleFullHash => app.log_columns.full_hash = !app.log_columns.full_hash,
+        Action::ToggleAbsoluteDate => {
+            app.log_columns.absolute_date = !app.log_columns.absolute_date
+        }
+        Action::ToggleRefs => app.log_columns.refs = !app.log_columns.refs,
+        // Commit graph and branch scope (both change the commit set, so reload)
+        Action::ToggleGraph => {
+            app.toggle_commit_graph();
+            load_commit_log(app, repo);
+        }
+        Action::ToggleAllRefs => {
+            app.log_query.filter.all = !app.log_query.filter.all;
+            load_commit_log(app, repo);
+        }
+        // Filter by path, author, date range or branch
+        Action::EditFilter => app.enter_log_filter(),
+        // Search the whole history via git log
+        Action::HistorySearch => app.enter_history_search(),
         // Search commit messages
-        (KeyCode::Char('/'), _) => app.enter_search(true),
-        (KeyCode::Char('?'), _) => app.enter_search(false),
-        (KeyCode::Char('n'), _) => app.search_next(),
-        (KeyCode::Char('N'), _) => app.search_prev(),
+        Action::SearchForward => app.enter_search(true),
+        Action::SearchBackward => app.enter_search(false),
+        Action::SearchNext => {
+            app.search_next(&[]);
+            load_more_commits(app, repo);
+        }
+        Action::SearchPrev => app.search_prev(&[]),
+        _ => {}
+    }
+}
+
+// ── Normal mode — Commit Detail screen ──────────────────────────
+
+fn handle_commit_detail_action(app: &mut App, action: Action, state: &RepoState, repo: &Path) {
+    match action {
+        // Back to commit log (clears a selection or search first)
+        Action::Back if app.selection.is_some() => app.selection = None,
+        Action::Back if app.search.active => app.clear_search(),
+        Action::Back => {
+            app.close_commit_detail();
+            app.recompute_visible_lines(current_files(app, state));
+        }
+        Action::Quit => app.should_quit = true,
+        // View the raw commit in external pager
+        Action::Pager => {
+            if let Some(detail) = &app.commit_detail {
+                if let Ok(raw) = git::git_show(repo, &detail.hash) {
+                    app.pager_content = Some(raw);
+                }
+            }
+        }
+        _ => app.with_detail_files(|app, files| {
+            handle_diff_nav_action(app, action, files, &[(app.view, files)]);
+        }),
+    }
+}
+
+// ── Normal mode — Timeline screen ───────────────────────────────
+
+fn handle_timeline_action(app: &mut App, action: Action, state: &RepoState, repo: &Path) {
+    match action {
+        Action::Back if app.selection.is_some() => app.selection = None,
+        Action::Back if app.search.active => app.clear_search(),
+        Action::Back => {
+            app.close_timeline();
+            app.recompute_visible_lines(current_files(app, state));
+        }
+        Action::Quit => app.should_quit = true,
+        Action::OlderSnapshot | Action::NewerSnapshot => {
+            if app.timeline_step(action == Action::OlderSnapshot) {
+                load_timeline_diff(app, repo);
+            }
+        }
+        Action::MarkSnapshot => {
+            app.toggle_timeline_mark();
+            load_timeline_diff(app, repo);
+        }
+        Action::Pager => {
+            let patch = export_patch(app, None, state);
+            if !patch.is_empty() {
+                app.pager_content = Some(patch);
+            }
+        }
+        _ => app.with_detail_files(|app, files| {
+            handle_diff_nav_action(app, action, files, &[(app.view, files)]);
+        }),
+    }
+}
+
+/// Take a timeline snapshot if the working tree changed since the last.
+fn record_snapshot(app: &mut App, repo: &Path, state: &RepoState) {
+    if !app.timeline.enabled() {
+        return;
+    }
+    let Ok(tree) = git::git_snapshot_tree(repo) else {
+        return;
+    };
+    let step = match app.timeline.latest() {
+        Some(prev) if prev.tree == tree => return,
+        Some(prev) => git::git_numstat(repo, &prev.tree, &tree).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let head = git::git_head(repo);
+    app.timeline.record(tree, head, state.branch.clone(), step);
+}
+
+/// Load the diff between the snapshots the timeline compares.
+fn load_timeline_diff(app: &mut App, repo: &Path) {
+    let Some((older, newer)) = app.timeline_compared() else {
+        return;
+    };
+    let from = match older {
+        Some(snapshot) => snapshot.tree.clone(),
+        None => newer.head.clone().unwrap_or_else(|| git::EMPTY_TREE.into()),
+    };
+    let to = newer.tree.clone();
+    match git::git_diff_trees(repo, &from, &to, app.path_filter.specs()) {
+        Ok(files) => app.set_timeline_diff(files),
+        Err(e) => app.message = Some(e.to_string()),
+    }
+}
+
+// ── Normal mode — Blame screen ──────────────────────────────────
+
+fn handle_blame_action(app: &mut App, action: Action, repo: &Path) {
+    let page = app.viewport_height.max(1) as isize;
+    match action {
+        Action::Back => app.close_blame(),
+        Action::Quit => app.should_quit = true,
+        // Navigate
+        Action::ScrollDown => app.blame_move(1),
+        Action::ScrollUp => app.blame_move(-1),
+        Action::ScrollTop => app.blame_move(isize::MIN),
+        Action::ScrollBottom => app.blame_move(isize::MAX),
+        Action::HalfPageDown => app.blame_move(page / 2),
+        Action::HalfPageUp => app.blame_move(-page / 2),
+        Action::PageDown => app.blame_move(page),
+        Action::PageUp => app.blame_move(-page),
+        // Open the commit that last touched this line
+        Action::Open => {
+            if let Some(line) = app.blame_selected().filter(|l| !l.is_uncommitted()) {
+                if let Ok(detail) = git::git_commit_detail(repo, &line.hash) {
+                    app.open_commit_detail(detail);
+                }
+            }
+        }
+        // Re-blame at the parent of the commit that last touched this line
+        Action::BlameParent => match app.blame_selected().and_then(|l| l.previous.clone()) {
+            Some((rev, path)) => match git::git_blame(repo, &path, Some(&rev)) {
+                Ok(lines) => app.push_blame(path, Some(rev), lines),
+                Err(_) => app.message = Some(format!("git blame failed for {path}")),
+            },
+            None => app.message = Some("line has no earlier history".into()),
+        },
+        // Back to the previous blame
+        Action::BlameBack => {
+            app.pop_blame();
+        }
+        // File history
+        Action::FileLog => {
+            if let Some(view) = app.blame_stack.last() {
+                app.log_query.filter.path = Some(view.path.clone());
+                open_commit_log(app, repo);
+                // The log replaces the blame screen, so the next blame
+                // starts afresh
+                if app.screen == Screen::CommitLog {
+                    app.blame_stack.clear();
+                }
+            }
+        }
         _ => {}
     }
 }
+
+// ── Normal mode — Commit composer ───────────────────────────────
+
+fn handle_commit_action(app: &mut App, action: Action, state: &RepoState, repo: &Path) {
+    let Some(draft) = app.commit_draft.as_mut() else {
+        app.screen = Screen::Diff;
+        return;
+    };
+    match action {
+        // The draft stays for the next `c` until it is committed
+        Action::Back => app.screen = Screen::Diff,
+        Action::Quit => app.should_quit = true,
+        // Options
+        Action::ToggleAmend => {
+            draft.options.amend = !draft.options.amend;
+            if draft.options.amend && draft.message.is_empty() {
+                if let Ok(msg) = git::git_commit_message(repo, "HEAD") {
+                    draft.message = msg.trim_end().to_string();
+                }
+            }
+        }
+        Action::ToggleSignoff => draft.options.signoff = !draft.options.signoff,
+        Action::ToggleNoVerify => draft.options.no_verify = !draft.options.no_verify,
+        // Write the message in $EDITOR
+        Action::EditMessage => {
+            let template = commit_template(&draft.message, &state.staged_diff);
+            match std::fs::write(&draft.message_file, template) {
+                Ok(()) => app.editor_request = Some(draft.message_file.clone()),
+                Err(e) => app.message = Some(format!("cannot write message file: {e}")),
+            }
+        }
+        Action::Commit => {
+            if draft.message.trim().is_empty() {
+                app.message = Some("empty commit message — press e to write one".into());
+                return;
+            }
+            if state.staged_diff.is_empty() && !draft.options.amend {
+                app.message = Some("nothing staged to commit".into());
+                return;
+            }
+            let result = std::fs::write(&draft.message_file, &draft.message)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| git::git_commit(repo, &draft.message_file, draft.options));
+            match result {
+                Ok(summary) => {
+                    app.commit_draft = None;
+                    app.screen = Screen::Diff;
+                    app.message = Some(summary);
+                }
+                Err(e) => {
+                    app.popup = Some(Popup {
+                        title: " Commit failed ".into(),
+                        body: e.to_string(),
+                    })
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Message file contents for the editor: the current draft followed by
+/// a commented summary of the staged files, like `git commit` does.
+fn commit_template(message: &str, staged: &[FileDiff]) -> String {
+    let mut out = String::from(message);
+    out.push_str("\n\n# Lines starting with '#' will be ignored.\n#\n# Staged changes:\n");
+    if staged.is_empty() {
+        out.push_str("#   (none)\n");
+    }
+    for fd in staged {
+        out.push_str(&format!(
+            "#   {}  +{} -{}\n",
+            fd.filename, fd.added, fd.removed
+        ));
+    }
+    out
+}
@@ -0,0 +1,308 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::diff::{DiffLine, FileDiff};
+
+/// Part of a diff screen to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Every file of the view.
+    View,
+    /// The file under the cursor.
+    File,
+    /// The hunk under the cursor.
+    Hunk,
+    /// The lines of the visual selection.
+    Selection,
+}
+
+impl ExportScope {
+    pub const NAMES: [&'static str; 4] = ["view", "file", "hunk", "selection"];
+
+    pub fn parse(word: &str) -> Option<Self> {
+        match word {
+            "view" => Some(ExportScope::View),
+            "file" => Some(ExportScope::File),
+            "hunk" => Some(ExportScope::Hunk),
+            "selection" | "sel" => Some(ExportScope::Selection),
+            _ => None,
+        }
+    }
+}
+
+/// Write `patch` to `path`. An existing file is only replaced with `force`,
+/// otherwise the error is `ErrorKind::AlreadyExists`.
+pub fn write(path: &Path, patch: &str, force: bool) -> io::Result<()> {
+    let mut file = if force {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?
+    } else {
+        OpenOptions::new().write(true).create_new(true).open(path)?
+    };
+    file.write_all(patch.as_bytes())
+}
+
+/// What to take from one file's diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Whole,
+    /// The hunk whose header is this line of `FileDiff::lines`.
+    Hunk(usize),
+    /// Changes on these lines of `FileDiff::lines`, inclusive.
+    Lines(usize, usize),
+}
+
+/// Build a patch `git apply` accepts from parts of file diffs.
+///
+/// Partial hunks keep only the chosen changes: other removed lines turn
+/// into context and other added lines are dropped, and hunk headers are
+/// recounted to match. Part of a file deletion becomes a modification of
+/// the file, as the rest of it stays. Files without a `diff --git` header
+/// (placeholders) and parts without changes are skipped.
+pub fn build(parts: &[(&FileDiff, Part)]) -> String {
+    let mut out = String::new();
+    for &(fd, part) in parts {
+        if fd.filename.is_empty() {
+            continue;
+        }
+        let first_hunk = fd
+            .lines
+            .iter()
+            .position(|dl| matches!(dl, DiffLine::Hunk(_)))
+            .unwrap_or(fd.lines.len());
+        let mut body = String::new();
+        // Lines the hunks written so far add to the new file
+        let mut shift = 0isize;
+        let mut start = first_hunk;
+        // Whether any change is left out
+        let mut partial = false;
+        while start < fd.lines.len() {
+            let end = fd.lines[start + 1..]
+                .iter()
+                .position(|dl| matches!(dl, DiffLine::Hunk(_)))
+                .map_or(fd.lines.len(), |i| start + 1 + i);
+            let chosen = |i: usize| match part {
+                Part::Whole => true,
+                Part::Hunk(h) => h == start,
+                Part::Lines(a, b) => (a..=b).contains(&i),
+            };
+            partial |= (start + 1..end).any(|i| {
+                matches!(fd.lines[i], DiffLine::Added(_) | DiffLine::Removed(_)) && !chosen(i)
+            });
+            if let Some((hunk, delta)) = write_hunk(&fd.lines, start, end, shift, chosen) {
+                body.push_str(&hunk);
+                shift += delta;
+            }
+            start = end;
+        }
+        // Headers alone only make sense for changes without hunks, such
+        // as binary files or mode changes
+        if body.is_empty() && (part != Part::Whole || first_hunk < fd.lines.len()) {
+            continue;
+        }
+        let headers = &fd.lines[..first_hunk];
+        let deleted = headers
+            .iter()
+            .any(|dl| dl.text().starts_with("deleted file mode "));
+        for dl in headers {
+            let text = dl.text();
+            if partial && deleted {
+                if text.starts_with("deleted file mode ") || text.starts_with("index ") {
+                    continue;
+                }
+                if text == "+++ /dev/null" {
+                    let old = headers
+                        .iter()
+                        .find_map(|dl| dl.text().strip_prefix("--- "))
+                        .unwrap_or_default();
+                    out.push_str(&format!("+++ {}\n", old.replacen("a/", "b/", 1)));
+                    continue;
+                }
+            }
+            out.push_str(text);
+            out.push('\n');
+        }
+        out.push_str(&body);
+    }
+    out
+}
+
+/// The hunk from `lines[start]` (its header) to `lines[end]` with only the
+/// `chosen` changes, and the lines it adds to the file. `None` if none of
+/// its changes are chosen.
+fn write_hunk(
+    lines: &[DiffLine],
+    start: usize,
+    end: usize,
+    shift: isize,
+    chosen: impl Fn(usize) -> bool,
+) -> Option<(String, isize)> {
+    let (old_start, heading) = parse_hunk_header(lines[start].text())?;
+    let mut body = String::new();
+    let (mut old, mut new, mut changes) = (0usize, 0usize, false);
+    // Whether the line a `\ No newline` marker belongs to was kept
+    let mut kept = true;
+    for (i, dl) in lines.iter().enumerate().take(end).skip(start + 1) {
+        let text = dl.text();
+        let line = match dl {
+            DiffLine::Added(_) if chosen(i) => {
+                new += 1;
+                changes = true;
+                text.to_string()
+            }
+            DiffLine::Added(_) => {
+                kept = false;
+                continue;
+            }
+            DiffLine::Removed(_) if chosen(i) => {
+                old += 1;
+                changes = true;
+                text.to_string()
+            }
+            DiffLine::Removed(_) => {
+                old += 1;
+                new += 1;
+                format!(" {}", &text[1..])
+            }
+            DiffLine::Context(_) if text.starts_with('\\') => {
+                if !kept {
+                    continue;
+                }
+                text.to_string()
+            }
+            _ => {
+                old += 1;
+                new += 1;
+                text.to_string()
+            }
+        };
+        kept = true;
+        body.push_str(&line);
+        body.push('\n');
+    }
+    if !changes {
+        return None;
+    }
+    // An empty range starts at the line before it
+    let old_first = if old == 0 { old_start + 1 } else { old_start };
+    let new_first = old_first.saturating_add_signed(shift);
+    let new_start = if new == 0 {
+        new_first.saturating_sub(1)
+    } else {
+        new_first
+    };
+    let header = format!("@@ -{old_start},{old} +{new_start},{new} @@{heading}\n");
+    Some((header + &body, new as isize - old as isize))
+}
+
+/// Old start line and trailing section heading of a `@@` header.
+fn parse_hunk_header(header: &str) -> Option<(usize, &str)> {
+    let (ranges, heading) = header.strip_prefix("@@ -")?.split_once(" @@")?;
+    let old = ranges.split(' ').next()?;
+    let start = old.split(',').next()?.parse().ok()?;
+    Some((start, heading))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::parse_files;
+
+    const RAW: &str = "\
+diff --git a/f b/f
+index 1111111..2222222 100644
+--- a/f
++++ b/f
+@@ -1,3 +1,3 @@ fn one()
+ a
+-b
++B
+ c
+@@ -10,2 +10,3 @@
+ x
++y
+ z
+";
+
+    #[test]
+    fn whole_file_round_trips() {
+        let files = parse_files(RAW);
+        assert_eq!(build(&[(&files[0], Part::Whole)]), RAW);
+    }
+
+    #[test]
+    fn single_hunk_is_renumbered() {
+        let files = parse_files(RAW);
+        // The second hunk's header is line 9
+        let patch = build(&[(&files[0], Part::Hunk(9))]);
+        assert!(patch.ends_with("+++ b/f\n@@ -10,2 +10,3 @@\n x\n+y\n z\n"));
+    }
+
+    #[test]
+    fn partial_selection_keeps_chosen_changes() {
+        let files = parse_files(RAW);
+        // Only `+B` (line 7): `-b` turns into context
+        let patch = build(&[(&files[0], Part::Lines(7, 7))]);
+        assert!(patch.ends_with("+++ b/f\n@@ -1,3 +1,4 @@ fn one()\n a\n b\n+B\n c\n"));
+        // Nothing changed in the chosen lines
+        assert!(build(&[(&files[0], Part::Lines(4, 5))]).is_empty());
+    }
+
+    #[test]
+    fn later_hunks_shift_by_earlier_ones() {
+        let files = parse_files(RAW);
+        // Both added lines, no removal: the first hunk grows by one
+        let patch = build(&[(&files[0], Part::Lines(7, 12))]);
+        assert!(patch.contains("@@ -1,3 +1,4 @@ fn one()\n"));
+        assert!(patch.contains("@@ -10,2 +11,3 @@\n"));
+    }
+
+    #[test]
+    fn write_only_overwrites_when_forced() {
+        let path = std::env::temp_dir().join(format!("git-monitor-patch-{}", std::process::id()));
+        write(&path, "one\n", false).unwrap();
+        let err = write(&path, "two\n", false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
+        write(&path, "two\n", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two\n");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn part_of_a_deletion_applies_as_a_modification() {
+        let raw = "\
+diff --git a/f b/f
+deleted file mode 100644
+index 1111111..0000000
+--- a/f
++++ /dev/null
+@@ -1,2 +0,0 @@
+-a
+-b
+";
+        let files = parse_files(raw);
+        assert_eq!(build(&[(&files[0], Part::Whole)]), raw);
+        let patch = build(&[(&files[0], Part::Lines(6, 6))]);
+        assert_eq!(
+            patch,
+            "diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -1,2 +1,1 @@\n-a\n b\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("git-monitor-apply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("f"), "a\nb\n").unwrap();
+        std::fs::write(dir.join("p.patch"), &patch).unwrap();
+        let status = std::process::Command::new("git")
+            .args(["apply", "--check", "p.patch"])
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Directories and globs the live diff is restricted to.
+///
+/// The specs are passed to git as pathspecs, and watcher events are
+/// matched by the same rules: a plain path covers everything below it, and
+/// in a glob `*` matches `/` too, so `*.proto` matches at any depth and
+/// `src/*.rs` covers `src/a/b.rs`. An empty filter matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    specs: Vec<String>,
+    /// Specs without glob characters, matched as paths.
+    paths: Vec<String>,
+    globs: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(specs: Vec<String>) -> Result<Self> {
+        let mut paths = Vec::new();
+        let mut globs = GlobSetBuilder::new();
+        for spec in &specs {
+            // Pathspec magic changes the rules, and `!` only excludes in
+            // gitignore files, so don't guess
+            if spec.starts_with(':') || spec.starts_with('!') {
+                bail!("unsupported path filter `{spec}`: use plain paths or globs");
+            }
+            if spec.contains(['*', '?', '[']) {
+                globs.add(Glob::new(spec)?);
+            } else {
+                paths.push(spec.trim_end_matches('/').to_string());
+            }
+        }
+        Ok(Self {
+            globs: globs.build()?,
+            paths,
+            specs,
+        })
+    }
+
+    /// Parse whitespace-separated specs as typed into the filter prompt.
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::new(input.split_whitespace().map(String::from).collect())
+    }
+
+    /// Pathspecs to pass to git after `--`.
+    pub fn specs(&self) -> &[String] {
+        &self.specs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// Whether `relative` (to the repo root) is covered by the filter.
+    pub fn matches(&self, relative: &Path) -> bool {
+        self.specs.is_empty()
+            || self.paths.iter().any(|p| relative.starts_with(p))
+            || self.globs.is_match(relative)
+    }
+}
+
+impl fmt::Display for PathFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.specs.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_and_globs() {
+        let filter = PathFilter::parse("services/api *.proto").unwrap();
+        assert!(filter.matches(Path::new("services/api/main.go")));
+        assert!(filter.matches(Path::new("api/v1/user.proto")));
+        assert!(!filter.matches(Path::new("services/web/index.ts")));
+        assert!(!filter.matches(Path::new("services/apiary/x.go")));
+        assert_eq!(filter.to_string(), "services/api *.proto");
+    }
+
+    #[test]
+    fn globs_cross_directories_as_in_git() {
+        // `git diff -- 'src/*.rs'` shows nested files, so they must
+        // trigger a refresh too
+        let filter = PathFilter::parse("src/*.rs").unwrap();
+        assert!(filter.matches(Path::new("src/main.rs")));
+        assert!(filter.matches(Path::new("src/a/b.rs")));
+        assert!(!filter.matches(Path::new("tests/a.rs")));
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = PathFilter::parse("  ").unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches(Path::new("anything")));
+    }
+
+    #[test]
+    fn magic_is_rejected() {
+        assert!(PathFilter::parse(":(exclude)vendor").is_err());
+        assert!(PathFilter::parse("!vendor").is_err());
+    }
+}
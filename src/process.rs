@@ -0,0 +1,148 @@
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+/// Process id of a run, cleared as soon as the process is reaped: the id
+/// is free for reuse from then on and must not be signalled.
+pub type Pid = Arc<Mutex<Option<u32>>>;
+
+/// How often a run is checked for having exited.
+const REAP_POLL: Duration = Duration::from_millis(50);
+
+/// How a run ended.
+#[derive(Debug)]
+pub struct Exit {
+    /// Exit code; `None` if the command was killed.
+    pub code: Option<i32>,
+    pub success: bool,
+    /// Standard output and error, interleaved.
+    pub output: String,
+    pub duration: Duration,
+    /// Whether it was stopped for running past its timeout.
+    pub timed_out: bool,
+}
+
+/// `command` run through `sh` from `dir`, with stderr sent to the same
+/// pipe as stdout so both streams stay in order.
+pub fn shell(command: &str, dir: &Path) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", &format!("exec 2>&1\n{command}")])
+        .current_dir(dir);
+    cmd
+}
+
+/// Start `cmd` in a process group of its own, so `kill` reaches every
+/// process it starts. A thread collects its output, stops it after
+/// `timeout` and hands the result to `done`.
+pub fn spawn(
+    mut cmd: Command,
+    timeout: Option<Duration>,
+    done: impl FnOnce(Exit) + Send + 'static,
+) -> Result<Pid> {
+    cmd.stdin(Stdio::null()).stdout(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let started = Instant::now();
+    let mut child = cmd.spawn()?;
+    let pid = Arc::new(Mutex::new(Some(child.id())));
+    let slot = Arc::clone(&pid);
+    let stdout = child.stdout.take();
+    thread::spawn(move || {
+        // Read on a thread of its own, so a run that never finishes its
+        // output can still time out
+        let reader = thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut out) = stdout {
+                let _ = out.read_to_end(&mut bytes);
+            }
+            bytes
+        });
+        let mut timed_out = false;
+        // Reap under the lock, so `kill` never signals a reused id
+        let status = loop {
+            let Ok(mut slot) = slot.lock() else {
+                break child.wait().ok();
+            };
+            match child.try_wait() {
+                Ok(None) => {
+                    if !timed_out && timeout.is_some_and(|t| started.elapsed() >= t) {
+                        timed_out = true;
+                        kill_tree(child.id());
+                    }
+                }
+                exited => {
+                    *slot = None;
+                    break exited.ok().flatten();
+                }
+            }
+            drop(slot);
+            thread::sleep(REAP_POLL);
+        };
+        let bytes = reader.join().unwrap_or_default();
+        done(Exit {
+            code: status.and_then(|s| s.code()),
+            success: status.is_some_and(|s| s.success()),
+            output: String::from_utf8_lossy(&bytes).into_owned(),
+            duration: started.elapsed(),
+            timed_out,
+        });
+    });
+    Ok(pid)
+}
+
+/// Stop the run `pid` and everything it started, unless it has ended.
+pub fn kill(pid: &Pid) {
+    // Held while signalling, so the process can't be reaped meanwhile
+    let Ok(pid) = pid.lock() else {
+        return;
+    };
+    if let Some(pid) = *pid {
+        kill_tree(pid);
+    }
+}
+
+/// Terminate process `pid` and its children.
+fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("kill");
+        cmd.args(["-TERM", "--", &format!("-{pid}")]);
+        cmd
+    };
+    #[cfg(not(unix))]
+    let mut cmd = {
+        let mut cmd = Command::new("taskkill");
+        cmd.args(["/F", "/T", "/PID", &pid.to_string()]);
+        cmd
+    };
+    let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn runs_past_their_timeout_are_stopped() {
+        let (tx, rx) = mpsc::channel();
+        let cmd = shell("echo started; sleep 10", Path::new("."));
+        let pid = spawn(cmd, Some(Duration::from_millis(200)), move |exit| {
+            let _ = tx.send(exit);
+        })
+        .unwrap();
+        let exit = rx.recv().unwrap();
+        assert!(exit.timed_out && !exit.success);
+        assert_eq!(exit.output, "started\n");
+        assert!(exit.duration < Duration::from_secs(5));
+        assert!(pid.lock().unwrap().is_none());
+    }
+}
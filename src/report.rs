@@ -0,0 +1,322 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::diff::{DiffLine, FileDiff};
+use crate::git::{FileStatus, RepoState};
+
+/// Output format of `--once`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format {
+    /// Status line and per-file stats, coloured on a terminal.
+    #[default]
+    Text,
+    /// The object `--json` prints for each refresh.
+    Json,
+    /// Tables for pull request comments and CI summaries.
+    Markdown,
+}
+
+/// One refresh of the repository, as written by `--json`.
+#[derive(Debug, Serialize)]
+pub struct Report<'a> {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub branch: &'a str,
+    pub head: Option<Head<'a>>,
+    pub staged_count: usize,
+    pub unstaged_count: usize,
+    /// Working tree files whose change triggered this refresh, newest
+    /// first. Empty for the first report and for changes inside `.git/`.
+    pub changed: Vec<String>,
+    pub staged: Vec<FileReport<'a>>,
+    pub unstaged: Vec<FileReport<'a>>,
+    pub untracked: Vec<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Head<'a> {
+    pub hash: &'a str,
+    pub message: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileReport<'a> {
+    pub path: &'a str,
+    /// Status letter from `git status`, e.g. `M`, `A`, `D` or `R`.
+    pub status: String,
+    pub added: usize,
+    pub removed: usize,
+    /// Only filled in when hunks are asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunks: Option<Vec<Hunk<'a>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hunk<'a> {
+    /// The `@@ -n,m +n,m @@` line.
+    pub header: &'a str,
+    /// Added, removed and context lines with their `+`, `-` or ` ` prefix.
+    pub lines: Vec<&'a str>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(state: &'a RepoState, changed: &[PathBuf], hunks: bool) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let head = state.last_commit_hash.as_deref().map(|hash| Head {
+            hash,
+            message: state.last_commit_message.as_deref().unwrap_or_default(),
+        });
+        Self {
+            time,
+            branch: &state.branch,
+            head,
+            staged_count: state.staged_count,
+            unstaged_count: state.unstaged_count,
+            changed: changed
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect(),
+            staged: file_reports(&state.staged_diff, &state.files, |f| f.index, hunks),
+            unstaged: file_reports(&state.unstaged_diff, &state.files, |f| f.worktree, hunks),
+            untracked: state
+                .files
+                .iter()
+                .filter(|f| f.index == '?')
+                .map(|f| f.path.as_str())
+                .collect(),
+        }
+    }
+
+    /// The report as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("reports always serialize")
+    }
+
+    /// The report in `format`, ending with a newline. Only text output is
+    /// coloured, and only with `color`.
+    pub fn render(&self, format: Format, color: bool) -> String {
+        match format {
+            Format::Text => self.to_text(color),
+            Format::Json => self.to_json() + "\n",
+            Format::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Exit status of `--once`: 0 with nothing to commit, 3 with only
+    /// staged changes, 4 with unstaged changes or untracked files. Errors
+    /// exit with 1 and usage errors with 2.
+    pub fn exit_code(&self) -> i32 {
+        if self.unstaged_count > 0 {
+            4
+        } else if self.staged_count > 0 {
+            3
+        } else {
+            0
+        }
+    }
+
+    fn short_head(&self) -> (&str, &str) {
+        match &self.head {
+            Some(head) => (head.hash.get(..7).unwrap_or(head.hash), head.message),
+            None => ("-------", "(no commits)"),
+        }
+    }
+
+    /// The status bar line followed by the staged, unstaged and untracked
+    /// files, with their hunks if the report has them.
+    fn to_text(&self, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{code}m{text}\x1b[0m")
+            } else {
+                text.to_string()
+            }
+        };
+        let (hash, message) = self.short_head();
+        let mut out = format!(
+            "{} | {} {message} | {} staged, {} unstaged\n",
+            paint("1", self.branch),
+            paint("33", hash),
+            self.staged_count,
+            self.unstaged_count
+        );
+        for (title, files, code) in [
+            ("Staged", &self.staged, "32"),
+            ("Unstaged", &self.unstaged, "31"),
+        ] {
+            if files.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n{title}:");
+            for file in files {
+                let _ = writeln!(
+                    out,
+                    "  {} {}  {} {}",
+                    paint(code, &file.status),
+                    file.path,
+                    paint("32", &format!("+{}", file.added)),
+                    paint("31", &format!("-{}", file.removed))
+                );
+                for hunk in file.hunks.iter().flatten() {
+                    let _ = writeln!(out, "    {}", paint("36", hunk.header));
+                    for line in &hunk.lines {
+                        let code = match line.chars().next() {
+                            Some('+') => "32",
+                            Some('-') => "31",
+                            _ => "0",
+                        };
+                        let _ = writeln!(out, "    {}", paint(code, line));
+                    }
+                }
+            }
+        }
+        if !self.untracked.is_empty() {
+            out.push_str("\nUntracked:\n");
+            for path in &self.untracked {
+                let _ = writeln!(out, "  {}", paint("31", path));
+            }
+        }
+        if self.exit_code() == 0 {
+            out.push_str("\nNothing to commit\n");
+        }
+        out
+    }
+
+    /// A heading, a table per diff and the untracked files, with hunks in
+    /// `diff` code blocks if the report has them.
+    fn to_markdown(&self) -> String {
+        let (hash, message) = self.short_head();
+        let mut out = format!(
+            "### `{}` at `{hash}` {message}\n\n{} staged, {} unstaged\n",
+            self.branch, self.staged_count, self.unstaged_count
+        );
+        for (title, files) in [("Staged", &self.staged), ("Unstaged", &self.unstaged)] {
+            if files.is_empty() {
+                continue;
+            }
+            let _ = write!(
+                out,
+                "\n**{title}**\n\n| Status | File | Added | Removed |\n|---|---|---:|---:|\n"
+            );
+            for file in files {
+                let _ = writeln!(
+                    out,
+                    "| {} | `{}` | +{} | -{} |",
+                    file.status, file.path, file.added, file.removed
+                );
+            }
+            for file in files.iter().filter(|f| f.hunks.is_some()) {
+                let _ = write!(out, "\n`{}`\n\n```diff\n", file.path);
+                for hunk in file.hunks.iter().flatten() {
+                    let _ = writeln!(out, "{}", hunk.header);
+                    for line in &hunk.lines {
+                        let _ = writeln!(out, "{line}");
+                    }
+                }
+                out.push_str("```\n");
+            }
+        }
+        if !self.untracked.is_empty() {
+            out.push_str("\n**Untracked**\n\n");
+            for path in &self.untracked {
+                let _ = writeln!(out, "- `{path}`");
+            }
+        }
+        out
+    }
+}
+
+/// Per-file stats of one diff, with the status letter `letter` picks from
+/// the file's `git status` entry. Placeholders without a filename are
+/// skipped.
+fn file_reports<'a>(
+    diff: &'a [FileDiff],
+    files: &[FileStatus],
+    letter: impl Fn(&FileStatus) -> char,
+    hunks: bool,
+) -> Vec<FileReport<'a>> {
+    diff.iter()
+        .filter(|fd| !fd.filename.is_empty())
+        .map(|fd| FileReport {
+            path: &fd.filename,
+            status: files
+                .iter()
+                .find(|f| f.path == fd.filename)
+                .map_or('M', &letter)
+                .to_string(),
+            added: fd.added,
+            removed: fd.removed,
+            hunks: hunks.then(|| file_hunks(fd)),
+        })
+        .collect()
+}
+
+fn file_hunks(fd: &FileDiff) -> Vec<Hunk<'_>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for dl in &fd.lines {
+        match dl {
+            DiffLine::Hunk(header) => hunks.push(Hunk {
+                header,
+                lines: Vec::new(),
+            }),
+            DiffLine::Added(text) | DiffLine::Removed(text) | DiffLine::Context(text) => {
+                if let Some(hunk) = hunks.last_mut() {
+                    hunk.lines.push(text);
+                }
+            }
+            DiffLine::FileHeader { .. } | DiffLine::Header(_) => {}
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::parse_files;
+
+    fn state(staged: &str, unstaged: &str) -> RepoState {
+        let mut state = RepoState::empty("");
+        state.branch = "main".into();
+        state.staged_diff = parse_files(staged);
+        state.unstaged_diff = parse_files(unstaged);
+        state.staged_count = state.staged_diff.len();
+        state.unstaged_count = state.unstaged_diff.len();
+        state
+    }
+
+    #[test]
+    fn exit_code_and_formats() {
+        let diff = "diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n+b\n";
+        let clean = state("", "");
+        assert_eq!(Report::new(&clean, &[], false).exit_code(), 0);
+        let staged = state(diff, "");
+        let report = Report::new(&staged, &[], false);
+        assert_eq!(report.exit_code(), 3);
+        assert_eq!(
+            report.render(Format::Text, false),
+            "main | ------- (no commits) | 1 staged, 0 unstaged\n\nStaged:\n  M f  +1 -1\n"
+        );
+        assert!(report
+            .render(Format::Markdown, false)
+            .contains("| M | `f` | +1 | -1 |\n"));
+        assert_eq!(Report::new(&state("", diff), &[], false).exit_code(), 4);
+    }
+
+    #[test]
+    fn hunks_split_on_headers() {
+        let files = parse_files(
+            "diff --git a/f b/f\n--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9,2 @@\n z\n+y\n",
+        );
+        let hunks = file_hunks(&files[0]);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header, "@@ -1 +1 @@");
+        assert_eq!(hunks[0].lines, ["-a", "+b"]);
+        assert_eq!(hunks[1].lines, [" z", "+y"]);
+    }
+}
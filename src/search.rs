@@ -0,0 +1,184 @@
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+
+use crate::diff::DiffLine;
+
+/// Which diff lines a search looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+    #[default]
+    All,
+    Added,
+    Removed,
+    /// File section headers only.
+    Files,
+}
+
+impl Scope {
+    /// Next scope, for cycling in the search prompt.
+    pub fn next(self) -> Self {
+        match self {
+            Scope::All => Scope::Added,
+            Scope::Added => Scope::Removed,
+            Scope::Removed => Scope::Files,
+            Scope::Files => Scope::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::All => "all lines",
+            Scope::Added => "added",
+            Scope::Removed => "removed",
+            Scope::Files => "filenames",
+        }
+    }
+
+    fn includes(self, dl: &DiffLine) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Added => matches!(dl, DiffLine::Added(_)),
+            Scope::Removed => matches!(dl, DiffLine::Removed(_)),
+            Scope::Files => matches!(dl, DiffLine::FileHeader { .. }),
+        }
+    }
+}
+
+/// A compiled search query.
+///
+/// A leading `+:`, `-:` or `f:` overrides the scope for one query. Matching
+/// is case-insensitive unless the pattern contains an uppercase letter.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+    scope: Scope,
+}
+
+impl Matcher {
+    /// Compile `query`, treating it as a regex unless `literal`. Returns
+    /// `None` when there is nothing to search for.
+    pub fn new(query: &str, literal: bool, scope: Scope) -> Result<Option<Self>> {
+        let (prefix_scope, pattern) = split_scope(query);
+        if pattern.is_empty() {
+            return Ok(None);
+        }
+        let source = if literal {
+            regex::escape(pattern)
+        } else {
+            pattern.to_string()
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!has_uppercase(pattern, literal))
+            .build()?;
+        Ok(Some(Self {
+            regex,
+            scope: prefix_scope.unwrap_or(scope),
+        }))
+    }
+
+    /// Byte ranges of non-empty matches in `dl`, if it is in scope. The
+    /// `+`, `-` or ` ` prefix of a changed or context line is not searched,
+    /// so `^` anchors at the start of the code.
+    pub fn find(&self, dl: &DiffLine) -> Vec<(usize, usize)> {
+        if !self.scope.includes(dl) {
+            return Vec::new();
+        }
+        let text = dl.text();
+        let skip = match dl {
+            DiffLine::Added(_) | DiffLine::Removed(_) => 1,
+            DiffLine::Context(_) if text.starts_with(' ') => 1,
+            _ => 0,
+        }
+        .min(text.len());
+        self.regex
+            .find_iter(&text[skip..])
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start() + skip, m.end() + skip))
+            .collect()
+    }
+
+    /// Whether `text` matches, ignoring scope — for lists such as the
+    /// commit log.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+/// Split off a scope prefix such as `+:`.
+fn split_scope(query: &str) -> (Option<Scope>, &str) {
+    for (prefix, scope) in [
+        ("+:", Scope::Added),
+        ("-:", Scope::Removed),
+        ("f:", Scope::Files),
+    ] {
+        if let Some(rest) = query.strip_prefix(prefix) {
+            return (Some(scope), rest);
+        }
+    }
+    (None, query)
+}
+
+/// Smart case: an uppercase letter makes the search case-sensitive. In a
+/// regex, escapes such as `\W` or `\S` don't count.
+fn has_uppercase(pattern: &str, literal: bool) -> bool {
+    let mut escaped = false;
+    for c in pattern.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if c == '\\' && !literal {
+            escaped = true;
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(query: &str) -> Matcher {
+        Matcher::new(query, false, Scope::All).unwrap().unwrap()
+    }
+
+    #[test]
+    fn smart_case() {
+        let line = DiffLine::Context("let Foo = foo;".into());
+        assert_eq!(matcher("foo").find(&line), [(4, 7), (10, 13)]);
+        assert_eq!(matcher("Foo").find(&line), [(4, 7)]);
+        assert_eq!(matcher(r"\Wfoo").find(&line), [(3, 7), (9, 13)]);
+    }
+
+    #[test]
+    fn regex_and_literal() {
+        let line = DiffLine::Context("call(a, b)".into());
+        assert_eq!(matcher("a.*b").find(&line), [(1, 9)]);
+        let literal = Matcher::new("(a", true, Scope::All).unwrap().unwrap();
+        assert_eq!(literal.find(&line), [(4, 6)]);
+        assert!(Matcher::new("(a", false, Scope::All).is_err());
+    }
+
+    #[test]
+    fn scope_prefixes() {
+        let added = DiffLine::Added("+foo".into());
+        let removed = DiffLine::Removed("-foo".into());
+        assert_eq!(matcher("+:foo").find(&added), [(1, 4)]);
+        assert!(matcher("+:foo").find(&removed).is_empty());
+        assert_eq!(matcher("-:foo").find(&removed), [(1, 4)]);
+        assert!(Matcher::new("f:", false, Scope::All).unwrap().is_none());
+    }
+
+    #[test]
+    fn anchors_skip_the_diff_prefix() {
+        let added = DiffLine::Added("+foo()".into());
+        let context = DiffLine::Context(" fn foo()".into());
+        assert_eq!(matcher("+:^foo").find(&added), [(1, 4)]);
+        assert_eq!(matcher("^fn").find(&context), [(1, 3)]);
+        assert!(matcher("^foo").find(&context).is_empty());
+        // A `+` is not part of the added code
+        assert!(matcher(r"\+foo").find(&added).is_empty());
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use ratatui::style::{Color, Modifier, Style};
+
+/// Every colour the UI uses, by role. Built-in themes fill these in and
+/// user themes from the config override individual entries.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub status_bar: Style,
+    pub border: Style,
+    /// Border of the pane with keyboard focus when the screen is split.
+    pub border_focus: Style,
+    pub help: Style,
+    /// Help bar while search results are active.
+    pub help_active: Style,
+    /// Help bar while typing into a prompt.
+    pub prompt: Style,
+    pub diff_header: Style,
+    pub hunk: Style,
+    pub added: Style,
+    pub removed: Style,
+    pub context: Style,
+    /// Collapsible per-file section header bar.
+    pub file_header: Style,
+    pub search_match: Style,
+    pub search_current: Style,
+    /// Lines a recent refresh brought in, patched onto the line's style.
+    pub changed: Style,
+    /// `changed` in the second half of its highlight time.
+    pub changed_fading: Style,
+    /// Lines in a visual selection, patched onto the line's style.
+    pub visual: Style,
+    /// Cursor row in lists (commit log, blame).
+    pub selected: Style,
+    pub hash: Style,
+    pub author: Style,
+    pub date: Style,
+    pub refs: Style,
+    /// Secondary text: labels, placeholders, list prefixes.
+    pub muted: Style,
+    pub trailer: Style,
+    /// Enabled checkbox in the commit composer.
+    pub enabled: Style,
+    pub error: Style,
+    /// Commit graph lanes, cycled.
+    pub lanes: Vec<Color>,
+    /// Blame ages: < day, < week, < month, < year, older.
+    pub age: [Color; 5],
+    pub uncommitted: Color,
+}
+
+/// Names accepted by `theme = "..."`.
+pub const BUILTIN: [&str; 4] = ["dark", "light", "high-contrast", "mono"];
+
+impl Theme {
+    /// The original palette, tuned for dark terminals.
+    pub fn dark() -> Self {
+        Self {
+            status_bar: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::DarkGray),
+            border_focus: Style::default().fg(Color::Cyan),
+            help: Style::default().fg(Color::DarkGray),
+            help_active: Style::default().fg(Color::Yellow),
+            prompt: Style::default().fg(Color::White).bg(Color::DarkGray),
+            diff_header: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            hunk: Style::default().fg(Color::Cyan),
+            added: Style::default().fg(Color::Green),
+            removed: Style::default().fg(Color::Red),
+            context: Style::default(),
+            file_header: Style::default()
+                .fg(Color::White)
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default().fg(Color::Black).bg(Color::Yellow),
+            search_current: Style::default().fg(Color::White).bg(Color::Red),
+            changed: Style::default().bg(Color::Indexed(238)),
+            changed_fading: Style::default().bg(Color::Indexed(235)),
+            visual: Style::default().bg(Color::Indexed(24)),
+            selected: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            hash: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            author: Style::default().fg(Color::Cyan),
+            date: Style::default().fg(Color::DarkGray),
+            refs: Style::default().fg(Color::Green),
+            muted: Style::default().fg(Color::DarkGray),
+            trailer: Style::default().fg(Color::Magenta),
+            enabled: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            lanes: vec![
+                Color::Red,
+                Color::Green,
+                Color::Yellow,
+                Color::Blue,
+                Color::Magenta,
+                Color::Cyan,
+            ],
+            age: [
+                Color::LightRed,
+                Color::LightYellow,
+                Color::Yellow,
+                Color::Green,
+                Color::DarkGray,
+            ],
+            uncommitted: Color::Magenta,
+        }
+    }
+
+    /// Darker foregrounds and light bars for light terminal backgrounds.
+    pub fn light() -> Self {
+        Self {
+            status_bar: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::Gray),
+            border_focus: Style::default().fg(Color::Blue),
+            help: Style::default().fg(Color::Black),
+            help_active: Style::default().fg(Color::Blue),
+            prompt: Style::default().fg(Color::Black).bg(Color::Gray),
+            diff_header: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            hunk: Style::default().fg(Color::Blue),
+            added: Style::default().fg(Color::Rgb(0, 110, 0)),
+            removed: Style::default().fg(Color::Rgb(170, 0, 0)),
+            context: Style::default(),
+            file_header: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            search_current: Style::default().fg(Color::White).bg(Color::Red),
+            changed: Style::default().bg(Color::Rgb(215, 228, 255)),
+            changed_fading: Style::default().bg(Color::Rgb(236, 242, 255)),
+            visual: Style::default().bg(Color::Rgb(255, 236, 179)),
+            selected: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            hash: Style::default()
+                .fg(Color::Rgb(150, 90, 0))
+                .add_modifier(Modifier::BOLD),
+            author: Style::default().fg(Color::Blue),
+            date: Style::default().fg(Color::Rgb(90, 90, 90)),
+            refs: Style::default().fg(Color::Rgb(0, 110, 0)),
+            muted: Style::default().fg(Color::Rgb(90, 90, 90)),
+            trailer: Style::default().fg(Color::Magenta),
+            enabled: Style::default()
+                .fg(Color::Rgb(0, 110, 0))
+                .add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            lanes: vec![
+                Color::Red,
+                Color::Rgb(0, 110, 0),
+                Color::Rgb(150, 90, 0),
+                Color::Blue,
+                Color::Magenta,
+                Color::Rgb(0, 120, 120),
+            ],
+            age: [
+                Color::Red,
+                Color::Rgb(180, 90, 0),
+                Color::Rgb(150, 120, 0),
+                Color::Rgb(0, 110, 0),
+                Color::Rgb(120, 120, 120),
+            ],
+            uncommitted: Color::Magenta,
+        }
+    }
+
+    /// Bold primaries on black with reversed selections.
+    pub fn high_contrast() -> Self {
+        let bold = |c: Color| Style::default().fg(c).add_modifier(Modifier::BOLD);
+        Self {
+            status_bar: Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::White),
+            border_focus: bold(Color::LightCyan),
+            help: Style::default().fg(Color::White),
+            help_active: bold(Color::LightYellow),
+            prompt: Style::default().fg(Color::Black).bg(Color::White),
+            diff_header: bold(Color::LightYellow),
+            hunk: bold(Color::LightCyan),
+            added: bold(Color::LightGreen),
+            removed: bold(Color::LightRed),
+            context: Style::default().fg(Color::White),
+            file_header: Style::default()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            search_current: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            changed: Style::default().bg(Color::Blue),
+            changed_fading: Style::default().bg(Color::DarkGray),
+            visual: Style::default().bg(Color::Magenta),
+            selected: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            hash: bold(Color::LightYellow),
+            author: bold(Color::LightCyan),
+            date: Style::default().fg(Color::White),
+            refs: bold(Color::LightGreen),
+            muted: Style::default().fg(Color::Gray),
+            trailer: bold(Color::LightMagenta),
+            enabled: bold(Color::LightGreen),
+            error: bold(Color::LightRed),
+            lanes: vec![
+                Color::LightRed,
+                Color::LightGreen,
+                Color::LightYellow,
+                Color::LightBlue,
+                Color::LightMagenta,
+                Color::LightCyan,
+            ],
+            age: [
+                Color::LightRed,
+                Color::LightYellow,
+                Color::Yellow,
+                Color::LightGreen,
+                Color::Gray,
+            ],
+            uncommitted: Color::LightMagenta,
+        }
+    }
+
+    /// No colours at all — used for `NO_COLOR`. Emphasis comes from
+    /// bold, underline and reverse video only.
+    pub fn mono() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let reversed = plain.add_modifier(Modifier::REVERSED);
+        Self {
+            status_bar: reversed,
+            border: plain,
+            border_focus: bold,
+            help: plain,
+            help_active: bold,
+            prompt: reversed,
+            diff_header: bold,
+            hunk: plain.add_modifier(Modifier::UNDERLINED),
+            added: bold,
+            removed: plain.add_modifier(Modifier::DIM),
+            context: plain,
+            file_header: reversed.add_modifier(Modifier::BOLD),
+            search_match: plain.add_modifier(Modifier::UNDERLINED),
+            search_current: reversed,
+            changed: plain.add_modifier(Modifier::ITALIC | Modifier::UNDERLINED),
+            changed_fading: plain.add_modifier(Modifier::ITALIC),
+            visual: reversed,
+            selected: reversed,
+            hash: bold,
+            author: plain,
+            date: plain,
+            refs: bold,
+            muted: plain,
+            trailer: plain,
+            enabled: bold,
+            error: bold,
+            lanes: vec![Color::Reset],
+            age: [Color::Reset; 5],
+            uncommitted: Color::Reset,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// Style for one of `age` by index, or `uncommitted`.
+    pub fn age_color(&self, bucket: usize, uncommitted: bool) -> Color {
+        if uncommitted {
+            self.uncommitted
+        } else {
+            self.age[bucket.min(self.age.len() - 1)]
+        }
+    }
+
+    /// Look up a style role by its config name.
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let style = match name {
+            "status_bar" => &mut self.status_bar,
+            "border" => &mut self.border,
+            "border_focus" => &mut self.border_focus,
+            "help" => &mut self.help,
+            "help_active" => &mut self.help_active,
+            "prompt" => &mut self.prompt,
+            "diff_header" => &mut self.diff_header,
+            "hunk" => &mut self.hunk,
+            "added" => &mut self.added,
+            "removed" => &mut self.removed,
+            "context" => &mut self.context,
+            "file_header" => &mut self.file_header,
+            "search_match" => &mut self.search_match,
+            "search_current" => &mut self.search_current,
+            "changed" => &mut self.changed,
+            "changed_fading" => &mut self.changed_fading,
+            "visual" => &mut self.visual,
+            "selected" => &mut self.selected,
+            "hash" => &mut self.hash,
+            "author" => &mut self.author,
+            "date" => &mut self.date,
+            "refs" => &mut self.refs,
+            "muted" => &mut self.muted,
+            "trailer" => &mut self.trailer,
+            "enabled" => &mut self.enabled,
+            "error" => &mut self.error,
+            _ => return None,
+        };
+        Some(style)
+    }
+
+    /// Apply a user theme table on top of this theme.
+    ///
+    /// Style roles take a style string (see `parse_style`); `lanes` and
+    /// `age` take arrays of colours; `uncommitted` takes a colour.
+    pub fn apply(&mut self, table: &BTreeMap<String, toml::Value>) -> Result<()> {
+        for (key, value) in table {
+            match key.as_str() {
+                "base" => {} // resolved by the caller
+                "lanes" => {
+                    let lanes = color_list(key, value)?;
+                    if lanes.is_empty() {
+                        bail!("lanes must list at least one colour");
+                    }
+                    self.lanes = lanes;
+                }
+                "age" => {
+                    let ages = color_list(key, value)?;
+                    self.age = ages
+                        .try_into()
+                        .map_err(|_| anyhow!("age must list exactly 5 colours"))?;
+                }
+                "uncommitted" => self.uncommitted = parse_color(as_str(key, value)?)?,
+                _ => {
+                    let spec = as_str(key, value)?;
+                    let style = self
+                        .style_mut(key)
+                        .ok_or_else(|| anyhow!("unknown theme key `{key}`"))?;
+                    *style = parse_style(spec)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn as_str<'a>(key: &str, value: &'a toml::Value) -> Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("`{key}` must be a string"))
+}
+
+fn color_list(key: &str, value: &toml::Value) -> Result<Vec<Color>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| anyhow!("`{key}` must be an array of colours"))?;
+    items.iter().map(|v| parse_color(as_str(key, v)?)).collect()
+}
+
+/// Parse a style string: `[fg] [on bg] [bold] [italic] [underline] [dim] [reverse]`.
+///
+/// Colours are names (`red`, `lightblue`, `darkgray`, `default`), hex
+/// (`#50fa7b`) for true colour, or 0–255 palette indices.
+/// Examples: `green`, `black on cyan bold`, `#f8f8f2 on #44475a`.
+pub fn parse_style(spec: &str) -> Result<Style> {
+    let mut style = Style::default();
+    let mut tokens = spec.split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "on" => {
+                let bg = tokens
+                    .next()
+                    .ok_or_else(|| anyhow!("`on` needs a colour in `{spec}`"))?;
+                style = style.bg(parse_color(bg)?);
+            }
+            "bold" => style = style.add_modifier(Modifier::BOLD),
+            "italic" => style = style.add_modifier(Modifier::ITALIC),
+            "underline" => style = style.add_modifier(Modifier::UNDERLINED),
+            "dim" => style = style.add_modifier(Modifier::DIM),
+            "reverse" => style = style.add_modifier(Modifier::REVERSED),
+            color => style = style.fg(parse_color(color)?),
+        }
+    }
+    Ok(style)
+}
+
+/// Parse a single colour name, `#rrggbb` or palette index.
+pub fn parse_color(s: &str) -> Result<Color> {
+    if let Some(hex) = s.strip_prefix('#') {
+        // `from_str_radix` alone would accept a sign
+        if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+            }
+        }
+        bail!("invalid hex colour `{s}`");
+    }
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(idx) = s.parse::<u8>() {
+            return Ok(Color::Indexed(idx));
+        }
+    }
+    let color = match s.to_lowercase().replace(['-', '_'], "").as_str() {
+        "default" | "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => bail!("unknown colour `{s}`"),
+    };
+    Ok(color)
+}
+
+/// Whether the `NO_COLOR` convention (<https://no-color.org>) is in effect.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_strings() {
+        let s = parse_style("black on #8be9fd bold").unwrap();
+        assert_eq!(s.fg, Some(Color::Black));
+        assert_eq!(s.bg, Some(Color::Rgb(0x8b, 0xe9, 0xfd)));
+        assert!(s.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(parse_style("dark-gray").unwrap().fg, Some(Color::DarkGray));
+        assert_eq!(parse_style("208").unwrap().fg, Some(Color::Indexed(208)));
+    }
+
+    #[test]
+    fn bad_style_strings() {
+        assert!(parse_style("chartreuse").is_err());
+        assert!(parse_style("red on").is_err());
+        assert!(parse_style("#12345").is_err());
+        assert!(parse_style("#+12345").is_err());
+        assert!(parse_style("+12").is_err());
+    }
+
+    #[test]
+    fn user_theme_rejects_unknown_keys() {
+        let table: BTreeMap<String, toml::Value> = toml::from_str("addded = \"red\"").unwrap();
+        assert!(Theme::dark().apply(&table).is_err());
+    }
+}
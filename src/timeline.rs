@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// The working tree as it was at one refresh.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Sequence number within the session, starting at 1.
+    pub id: usize,
+    /// Tree object holding the working tree, untracked files included.
+    pub tree: String,
+    /// HEAD commit at the time, if there was one.
+    pub head: Option<String>,
+    pub branch: String,
+    pub taken: Instant,
+    /// Files changed since the previous snapshot, with lines added and
+    /// removed. Empty for the first one.
+    pub step: Vec<(String, usize, usize)>,
+}
+
+impl Snapshot {
+    /// Lines added and removed since the previous snapshot.
+    pub fn step_totals(&self) -> (usize, usize) {
+        self.step
+            .iter()
+            .fold((0, 0), |(a, r), (_, added, removed)| {
+                (a + added, r + removed)
+            })
+    }
+}
+
+/// Snapshots taken during the session, oldest first. Once `capacity` is
+/// reached, the oldest is dropped for each new one.
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+    next_id: usize,
+}
+
+impl Timeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            capacity,
+            next_id: 1,
+        }
+    }
+
+    /// Whether snapshots are taken at all.
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Append a snapshot of `tree` unless it matches the latest one.
+    /// Returns whether it was added.
+    pub fn record(
+        &mut self,
+        tree: String,
+        head: Option<String>,
+        branch: String,
+        step: Vec<(String, usize, usize)>,
+    ) -> bool {
+        if !self.enabled() || self.latest().is_some_and(|s| s.tree == tree) {
+            return false;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            id: self.next_id,
+            tree,
+            head,
+            branch,
+            taken: Instant::now(),
+            step,
+        });
+        self.next_id += 1;
+        true
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+
+    /// Snapshot with sequence number `id`, if it is still kept.
+    pub fn get(&self, id: usize) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| s.id == id)
+    }
+
+    /// Snapshot taken just before `id`.
+    pub fn before(&self, id: usize) -> Option<&Snapshot> {
+        self.snapshots.iter().rev().find(|s| s.id < id)
+    }
+
+    /// Snapshot taken just after `id`.
+    pub fn after(&self, id: usize) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| s.id > id)
+    }
+
+    /// Snapshots, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Snapshot> {
+        self.snapshots.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timeline: &mut Timeline, tree: &str) -> bool {
+        timeline.record(tree.into(), None, "main".into(), Vec::new())
+    }
+
+    #[test]
+    fn drops_oldest_and_skips_repeats() {
+        let mut timeline = Timeline::new(2);
+        assert!(record(&mut timeline, "a"));
+        assert!(!record(&mut timeline, "a"));
+        assert!(record(&mut timeline, "b"));
+        assert!(record(&mut timeline, "c"));
+        let trees: Vec<&str> = timeline.iter().map(|s| s.tree.as_str()).collect();
+        assert_eq!(trees, ["b", "c"]);
+        // Ids keep counting past dropped snapshots
+        assert_eq!(timeline.latest().map(|s| s.id), Some(3));
+        assert!(timeline.get(1).is_none());
+        assert_eq!(timeline.before(3).map(|s| s.id), Some(2));
+        assert!(timeline.after(3).is_none());
+    }
+
+    #[test]
+    fn disabled_records_nothing() {
+        let mut timeline = Timeline::new(0);
+        assert!(!record(&mut timeline, "a"));
+        assert!(timeline.latest().is_none());
+    }
+}
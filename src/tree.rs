@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+
+/// A changed file to place in the tree.
+#[derive(Debug, Clone)]
+pub struct TreeEntry {
+    pub path: String,
+    /// Status letter: `M`, `A`, `D`, `R`, `?`, ...
+    pub status: char,
+    /// `+added -removed`, if the file has a diff section.
+    pub stats: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeNode {
+    /// A directory; `path` has no trailing slash.
+    Dir { path: String, collapsed: bool },
+    File {
+        path: String,
+        status: char,
+        stats: Option<(usize, usize)>,
+    },
+}
+
+/// One visible row of the file tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeRow {
+    pub depth: usize,
+    /// Last path component.
+    pub name: String,
+    pub node: TreeNode,
+}
+
+impl TreeRow {
+    /// File path of a file row.
+    pub fn file_path(&self) -> Option<&str> {
+        match &self.node {
+            TreeNode::File { path, .. } => Some(path),
+            TreeNode::Dir { .. } => None,
+        }
+    }
+}
+
+/// Lay out `entries` as a directory tree, directories first at each level.
+/// Contents of directories in `collapsed` are left out.
+pub fn build(entries: &[TreeEntry], collapsed: &HashSet<String>) -> Vec<TreeRow> {
+    let mut sorted: Vec<&TreeEntry> = entries.iter().collect();
+    // Sorting on components with files after directories groups each
+    // directory's contents together
+    sorted.sort_by(|a, b| sort_key(&a.path).cmp(&sort_key(&b.path)));
+
+    let mut rows = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    for entry in sorted {
+        let parts: Vec<&str> = entry.path.split('/').collect();
+        let (dirs, name) = parts.split_at(parts.len() - 1);
+
+        // Close directories the previous entry was in but this one isn't
+        let shared = open.iter().zip(dirs).take_while(|(a, b)| a == b).count();
+        open.truncate(shared);
+
+        let mut hidden = open
+            .iter()
+            .enumerate()
+            .any(|(i, _)| collapsed.contains(&dirs[..=i].join("/")));
+        for (depth, dir) in dirs.iter().enumerate().skip(shared) {
+            open.push(dir);
+            let path = dirs[..=depth].join("/");
+            let is_collapsed = collapsed.contains(&path);
+            if !hidden {
+                rows.push(TreeRow {
+                    depth,
+                    name: dir.to_string(),
+                    node: TreeNode::Dir {
+                        path,
+                        collapsed: is_collapsed,
+                    },
+                });
+            }
+            hidden |= is_collapsed;
+        }
+        if !hidden {
+            rows.push(TreeRow {
+                depth: dirs.len(),
+                name: name[0].to_string(),
+                node: TreeNode::File {
+                    path: entry.path.clone(),
+                    status: entry.status,
+                    stats: entry.stats,
+                },
+            });
+        }
+    }
+    rows
+}
+
+/// Components with a leading flag that puts files after directories.
+fn sort_key(path: &str) -> Vec<(bool, &str)> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let last = parts.len() - 1;
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| (i == last, part))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            status: 'M',
+            stats: None,
+        }
+    }
+
+    fn outline(rows: &[TreeRow]) -> Vec<String> {
+        rows.iter()
+            .map(|r| {
+                let suffix = if r.file_path().is_some() { "" } else { "/" };
+                format!("{}{}{suffix}", "  ".repeat(r.depth), r.name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn directories_group_their_files() {
+        let entries = [
+            entry("README.md"),
+            entry("src/ui.rs"),
+            entry("src/git/log.rs"),
+            entry("src/app.rs"),
+        ];
+        let rows = build(&entries, &HashSet::new());
+        assert_eq!(
+            outline(&rows),
+            [
+                "src/",
+                "  git/",
+                "    log.rs",
+                "  app.rs",
+                "  ui.rs",
+                "README.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsed_directories_hide_contents() {
+        let entries = [entry("src/git/log.rs"), entry("src/app.rs"), entry("x.rs")];
+        let collapsed = HashSet::from(["src/git".to_string()]);
+        let rows = build(&entries, &collapsed);
+        assert_eq!(outline(&rows), ["src/", "  git/", "  app.rs", "x.rs"]);
+        assert_eq!(
+            rows[1].node,
+            TreeNode::Dir {
+                path: "src/git".into(),
+                collapsed: true
+            }
+        );
+    }
+}
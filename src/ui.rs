@@ -1,20 +1,37 @@
+use std::time::{Duration, Instant};
+
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::app::{App, DiffView, InputMode, Screen, SearchState};
+use crate::app::{App, ClickMap, DiffView, Follow, InputMode, Pane, Popup, Screen};
 use crate::diff::DiffLine;
-use crate::git::RepoState;
+use crate::git::{CommitDetail, CommitEntry, RepoState};
+use crate::keymap::{Action, KeyMap};
+use crate::theme::Theme;
+use crate::tree::TreeNode;
 
 /// Render the full TUI frame.
 pub fn draw(frame: &mut Frame, app: &mut App, state: &RepoState) {
+    app.click_map = ClickMap::default();
     match app.screen {
         Screen::Diff => draw_diff_screen(frame, app, state),
         Screen::CommitLog => draw_commit_log_screen(frame, app, state),
+        Screen::CommitDetail => draw_commit_detail_screen(frame, app, state),
+        Screen::Blame => draw_blame_screen(frame, app, state),
+        Screen::Commit => draw_commit_screen(frame, app, state),
+        Screen::Timeline => draw_timeline_screen(frame, app, state),
+    }
+    if app.help.is_some() {
+        draw_help_overlay(frame, app);
+    }
+    if let Some(popup) = &app.popup {
+        draw_popup(frame, &app.theme, popup);
     }
 }
 
@@ -30,40 +47,331 @@ fn draw_diff_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
         ])
         .split(frame.area());
 
-    draw_status_bar(frame, state, chunks[0]);
+    draw_status_bar(frame, app, state, chunks[0]);
+
+    let mut main_area = chunks[1];
+    let hooks = app.hook_panel && !app.hooks.is_empty();
+    let check = app.check_panel && app.check.enabled();
+    if hooks || check {
+        let height = (main_area.height / 3).max(3);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(height)])
+            .split(main_area);
+        main_area = rows[0];
+        // Side by side when both are open
+        let panels = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(if hooks && check {
+                vec![Constraint::Ratio(1, 2); 2]
+            } else {
+                vec![Constraint::Min(1)]
+            })
+            .split(rows[1]);
+        if hooks {
+            draw_hook_panel(frame, app, panels[0]);
+        }
+        if check {
+            draw_check_panel(frame, app, panels[panels.len() - 1]);
+        }
+    }
+
+    let mut diff_area = main_area;
+    if app.sidebar {
+        let width = (main_area.width / 3).clamp(20, 40).min(main_area.width / 2);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(width), Constraint::Min(1)])
+            .split(main_area);
+        draw_file_tree(frame, app, state, panes[0]);
+        diff_area = panes[1];
+    }
 
-    let view_label = match app.view {
-        DiffView::Unstaged => " Unstaged Changes ",
-        DiffView::Staged => " Staged Changes ",
+    let view_label = match (app.view, &app.base) {
+        (DiffView::Unstaged, None) => " Unstaged Changes ".to_string(),
+        (DiffView::Staged, None) => " Staged Changes ".to_string(),
+        (DiffView::Unstaged, Some(base)) => format!(" Working Tree vs {base} "),
+        (DiffView::Staged, Some(base)) => format!(" Index vs {base} "),
     };
+    draw_diff_lines(frame, app, diff_area, view_label);
 
+    draw_help_bar(frame, app, chunks[2]);
+}
+
+/// Render `visible_lines` into `area` with scroll, fold and search state.
+fn draw_diff_lines(frame: &mut Frame, app: &mut App, area: Rect, title: String) {
     app.diff_line_count = app.visible_lines.len() as u16;
-    app.viewport_height = chunks[1].height.saturating_sub(2);
+    app.viewport_height = area.height.saturating_sub(2);
 
     let max_scroll = app.diff_line_count.saturating_sub(app.viewport_height);
     if app.scroll > max_scroll {
         app.scroll = max_scroll;
     }
 
-    let term_width = chunks[1].width.saturating_sub(2) as usize; // minus block borders
+    let term_width = area.width.saturating_sub(2) as usize; // minus block borders
+    let spots = app.visible_matches();
+    // Change marks are kept for the live diff only
+    let track = app.screen == Screen::Diff;
+    let selected = app.selection_range();
+    let (mut file, mut offset) = ("", 0);
     let styled_lines: Vec<Line> = app
         .visible_lines
         .iter()
         .enumerate()
-        .map(|(i, dl)| highlight_diff_line(dl, i, &app.search, &app.collapsed, term_width))
+        .map(|(i, dl)| {
+            let changed_at = match dl {
+                DiffLine::FileHeader { filename, .. } => {
+                    (file, offset) = (filename.as_str(), 0);
+                    app.changes.touched(app.view, file)
+                }
+                _ => {
+                    offset += 1;
+                    app.changes.line(app.view, file, offset - 1)
+                }
+            };
+            let change = changed_at
+                .filter(|_| track)
+                .and_then(|at| change_style(&app.theme, at, app.change_ttl));
+            let matches = spots.get(&i).map(Vec::as_slice).unwrap_or_default();
+            let line = highlight_diff_line(
+                dl,
+                matches,
+                change.is_some(),
+                &app.collapsed,
+                &app.theme,
+                term_width,
+            );
+            let line = match change {
+                Some(style) if !matches!(dl, DiffLine::FileHeader { .. }) => {
+                    line.patch_style(style)
+                }
+                _ => line,
+            };
+            match selected {
+                Some((first, last)) if (first..=last).contains(&i) => {
+                    line.patch_style(app.theme.visual)
+                }
+                _ => line,
+            }
+        })
         .collect();
 
+    let title = match selected {
+        Some((first, last)) => format!("{title}— {} lines selected ", last - first + 1),
+        None => title,
+    };
+
+    let focused = app.screen == Screen::Diff && app.sidebar && app.focus == Pane::Diff;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(if focused {
+            app.theme.border_focus
+        } else {
+            app.theme.border
+        });
+    app.click_map.content = block.inner(area);
+    app.click_map.first_item = app.scroll as usize;
+
     let diff_widget = Paragraph::new(styled_lines)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(view_label)
-                .border_style(Style::default().fg(Color::DarkGray)),
-        )
+        .block(block)
         .scroll((app.scroll, 0));
-    frame.render_widget(diff_widget, chunks[1]);
+    frame.render_widget(diff_widget, area);
+}
 
-    draw_help_bar(frame, app, chunks[2]);
+/// Output of the latest hook run, scrolled to its end.
+fn draw_hook_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let (title, style, output) = match app.hooks.latest() {
+        Some((name, result)) => {
+            let outcome = match (result.success, result.code, result.timed_out) {
+                (_, _, Some(timeout)) => format!("timed out after {}", duration(timeout)),
+                (true, _, _) => "ok".to_string(),
+                (false, Some(code), _) => format!("exit {code}"),
+                (false, None, _) => "killed".to_string(),
+            };
+            let style = if result.success {
+                app.theme.enabled
+            } else {
+                app.theme.error
+            };
+            let title = match result.timed_out {
+                Some(_) => format!(" {name} — {outcome} "),
+                None => format!(" {name} — {outcome} in {} ", duration(result.duration)),
+            };
+            (title, style, result.output.as_str())
+        }
+        None => (
+            " Hooks ".to_string(),
+            app.theme.border,
+            "No hook has run yet",
+        ),
+    };
+    let height = area.height.saturating_sub(2) as usize;
+    let lines: Vec<&str> = output.lines().collect();
+    let tail: Vec<Line> = lines[lines.len().saturating_sub(height)..]
+        .iter()
+        .map(|line| Line::raw(*line))
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(title, style))
+        .border_style(app.theme.border);
+    frame.render_widget(Paragraph::new(tail).block(block), area);
+}
+
+/// Output of the latest check run. After a jump to one of its `file:line`
+/// references, the line it came from is highlighted and kept in view;
+/// otherwise the end of the output is shown.
+fn draw_check_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let check = &app.check;
+    let name = check.name();
+    let (title, style) = match (check.started(), &check.last) {
+        (Some(started), _) => (
+            format!(" {name} — running {} ", duration(started.elapsed())),
+            app.theme.muted,
+        ),
+        (None, Some(result)) => {
+            let outcome = match (result.success, result.code) {
+                (true, _) => "passed".to_string(),
+                (false, Some(code)) => format!("failed (exit {code})"),
+                (false, None) => "killed".to_string(),
+            };
+            let refs = match check.refs.len() {
+                0 => String::new(),
+                1 => " — 1 reference".to_string(),
+                n => format!(" — {n} references"),
+            };
+            let style = if result.success {
+                app.theme.enabled
+            } else {
+                app.theme.error
+            };
+            (
+                format!(" {name} {outcome} in {}{refs} ", duration(result.duration)),
+                style,
+            )
+        }
+        (None, None) => (format!(" {name} "), app.theme.border),
+    };
+    let output = check.last.as_ref().map_or("", |r| r.output.as_str());
+    let lines: Vec<&str> = output.lines().collect();
+    let height = area.height.saturating_sub(2) as usize;
+    let current = check
+        .current_ref
+        .and_then(|i| check.refs.get(i))
+        .map(|r| r.output_line);
+    let first = match current {
+        Some(line) => line
+            .saturating_sub(height / 2)
+            .min(lines.len().saturating_sub(height)),
+        None => lines.len().saturating_sub(height),
+    };
+    let shown: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, line)| {
+            if Some(i) == current {
+                Line::styled(*line, app.theme.selected)
+            } else {
+                Line::raw(*line)
+            }
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(title, style))
+        .border_style(app.theme.border);
+    frame.render_widget(Paragraph::new(shown).block(block), area);
+}
+
+/// Changed files as a directory tree with status letters and `+/-` stats.
+/// While the diff has focus the cursor follows the file being viewed.
+fn draw_file_tree(frame: &mut Frame, app: &mut App, state: &RepoState, area: Rect) {
+    let files = match app.view {
+        DiffView::Unstaged => &state.unstaged_diff,
+        DiffView::Staged => &state.staged_diff,
+    };
+    let rows = app.file_tree(&state.files, files);
+    if app.focus == Pane::Diff {
+        let current = app.file_at_scroll();
+        if let Some(i) = rows
+            .iter()
+            .position(|r| r.file_path().is_some() && r.file_path() == current.as_deref())
+        {
+            app.tree_selected = i;
+        }
+    }
+    app.tree_selected = app.tree_selected.min(rows.len().saturating_sub(1));
+
+    let theme = &app.theme;
+    let focused = app.focus == Pane::Sidebar;
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let indent = "  ".repeat(row.depth);
+            let mut spans = match &row.node {
+                TreeNode::Dir { collapsed, .. } => {
+                    let marker = if *collapsed { "▸" } else { "▾" };
+                    vec![Span::styled(
+                        format!("{indent}{marker} {}/", row.name),
+                        theme.diff_header,
+                    )]
+                }
+                TreeNode::File { status, stats, .. } => {
+                    let status_style = match status {
+                        'A' => theme.added,
+                        'D' => theme.removed,
+                        'R' | 'C' => theme.refs,
+                        '?' => theme.muted,
+                        _ => theme.hunk,
+                    };
+                    let mut spans = vec![
+                        Span::raw(indent),
+                        Span::styled(format!("{status} "), status_style),
+                        Span::styled(row.name.clone(), theme.context),
+                    ];
+                    if let Some((added, removed)) = stats {
+                        spans.push(Span::styled(format!(" +{added}"), theme.added));
+                        spans.push(Span::styled(format!(" -{removed}"), theme.removed));
+                    }
+                    spans
+                }
+            };
+            if i == app.tree_selected {
+                let style = if focused {
+                    theme.selected
+                } else {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                };
+                for span in &mut spans {
+                    span.style = style;
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " Files ({}) ",
+            rows.iter().filter(|r| r.file_path().is_some()).count()
+        ))
+        .border_style(if focused {
+            theme.border_focus
+        } else {
+            theme.border
+        });
+    let inner = block.inner(area);
+    let list_scroll = (app.tree_selected as u16).saturating_sub(inner.height.saturating_sub(1));
+    app.click_map.sidebar = inner;
+    app.click_map.sidebar_first_item = list_scroll as usize;
+
+    let widget = Paragraph::new(lines).block(block).scroll((list_scroll, 0));
+    frame.render_widget(widget, area);
 }
 
 // ── Commit Log screen ───────────────────────────────────────────
@@ -78,98 +386,120 @@ fn draw_commit_log_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
         ])
         .split(frame.area());
 
-    draw_status_bar(frame, state, chunks[0]);
+    draw_status_bar(frame, app, state, chunks[0]);
 
     app.viewport_height = chunks[1].height.saturating_sub(2);
 
     // Responsive column layout based on available terminal width.
-    // Layout: [prefix 2][hash 8][sp 1][message ...][sp 2][author N][sp 2][date N]
+    // Layout: [prefix 2][hash H][sp 1][refs + message ...][sp 2][author N][sp 2][date N]
     // The message column gets whatever space remains after fixed columns.
+    let cols = app.log_columns;
     let available = chunks[1].width.saturating_sub(2) as usize; // minus block borders
+    let hash_width = if cols.full_hash { 40 } else { 8 };
+    let date_of = |e: &CommitEntry| -> String {
+        if cols.absolute_date {
+            e.date_absolute.clone()
+        } else {
+            e.date_relative.clone()
+        }
+    };
     let date_width = app
         .commit_log
         .iter()
-        .map(|e| e.date_relative.len())
+        .map(|e| date_of(e).width())
         .max()
         .unwrap_or(8)
         .min(16);
     let author_width = app
         .commit_log
         .iter()
-        .map(|e| e.author.len())
+        .map(|e| e.author.width())
         .max()
         .unwrap_or(8)
         .min(20);
-    // Fixed: prefix(2) + hash(8) + gaps(1+2+2) = 15
-    let fixed = 15 + author_width + date_width;
+    let graph_width = if cols.graph {
+        app.commit_graph.iter().map(Vec::len).max().unwrap_or(0)
+    } else {
+        0
+    };
+    // Fixed: prefix(2) + graph + hash + gaps(1+2+2)
+    let fixed = 7 + graph_width + hash_width + author_width + date_width;
     let msg_width = available.saturating_sub(fixed).max(10);
 
+    let theme = &app.theme;
+    let matcher = app.search.matcher();
     let mut lines: Vec<Line> = Vec::new();
     for (i, entry) in app.commit_log.iter().enumerate() {
         let is_selected = i == app.commit_log_selected;
 
         let prefix = if is_selected { "> " } else { "  " };
-        let msg_display = truncate_str(&entry.message, msg_width);
-        let author_display = truncate_str(&entry.author, author_width);
+        let hash = if cols.full_hash {
+            &entry.full_hash
+        } else {
+            &entry.hash
+        };
+        let refs = if cols.refs && !entry.refs.is_empty() {
+            format!("({}) ", entry.refs)
+        } else {
+            String::new()
+        };
+        let refs_display = truncate_str(&refs, msg_width);
+        let msg_display = truncate_str(
+            &entry.message,
+            msg_width.saturating_sub(refs_display.width()),
+        );
+        let msg_pad = msg_width.saturating_sub(refs_display.width() + msg_display.width());
+        let author_display = pad(truncate_str(&entry.author, author_width), author_width);
 
-        if is_selected {
-            let text = format!(
-                "{prefix}{:<8} {:<msg_w$}  {:<auth_w$}  {}",
-                entry.hash,
-                msg_display,
-                author_display,
-                entry.date_relative,
-                msg_w = msg_width,
-                auth_w = author_width,
-            );
-            lines.push(Line::from(Span::styled(
-                text,
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )));
+        let is_search_match = matcher.as_ref().is_some_and(|m| {
+            m.is_match(&entry.message) || m.is_match(&entry.author) || m.is_match(&entry.hash)
+        });
+        let underline = if is_search_match {
+            Modifier::UNDERLINED
         } else {
-            let is_search_match = app.search.active && !app.search.query.is_empty() && {
-                let q = app.search.query.to_lowercase();
-                entry.message.to_lowercase().contains(&q)
-                    || entry.author.to_lowercase().contains(&q)
-                    || entry.hash.to_lowercase().contains(&q)
-            };
-            let underline = if is_search_match {
-                Modifier::UNDERLINED
-            } else {
-                Modifier::empty()
-            };
+            Modifier::empty()
+        };
+        let plain = Style::default().add_modifier(underline);
 
-            lines.push(Line::from(vec![
-                Span::styled(
-                    prefix.to_string(),
-                    Style::default().fg(Color::DarkGray).add_modifier(underline),
-                ),
-                Span::styled(
-                    format!("{:<8}", entry.hash),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD | underline),
-                ),
-                Span::styled(" ".to_string(), Style::default().add_modifier(underline)),
-                Span::styled(
-                    format!("{:<msg_w$}", msg_display, msg_w = msg_width),
-                    Style::default().fg(Color::White).add_modifier(underline),
-                ),
-                Span::styled("  ".to_string(), Style::default().add_modifier(underline)),
-                Span::styled(
-                    format!("{:<auth_w$}", author_display, auth_w = author_width),
-                    Style::default().fg(Color::Cyan).add_modifier(underline),
-                ),
-                Span::styled("  ".to_string(), Style::default().add_modifier(underline)),
-                Span::styled(
-                    entry.date_relative.clone(),
-                    Style::default().fg(Color::DarkGray).add_modifier(underline),
-                ),
-            ]));
+        let mut spans = vec![Span::styled(
+            prefix.to_string(),
+            theme.muted.add_modifier(underline),
+        )];
+        if cols.graph {
+            let row = app
+                .commit_graph
+                .get(i)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for cell in row {
+                spans.push(Span::styled(
+                    cell.glyph.to_string(),
+                    plain.fg(theme.lanes[cell.lane % theme.lanes.len()]),
+                ));
+            }
+            spans.push(Span::raw(" ".repeat(graph_width - row.len())));
         }
+        spans.extend([
+            Span::styled(
+                format!("{hash:<hash_width$}"),
+                theme.hash.add_modifier(underline),
+            ),
+            Span::styled(" ".to_string(), plain),
+            Span::styled(refs_display, theme.refs.add_modifier(underline)),
+            Span::styled(msg_display, theme.context.add_modifier(underline)),
+            Span::styled(" ".repeat(msg_pad), plain),
+            Span::styled("  ".to_string(), plain),
+            Span::styled(author_display, theme.author.add_modifier(underline)),
+            Span::styled("  ".to_string(), plain),
+            Span::styled(date_of(entry), theme.date.add_modifier(underline)),
+        ]);
+
+        if is_selected {
+            for span in &mut spans {
+                span.style = theme.selected;
+            }
+        }
+        lines.push(Line::from(spans));
     }
 
     // Keep selected item in view
@@ -179,22 +509,492 @@ fn draw_commit_log_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
         0
     };
 
-    let list_widget = Paragraph::new(lines)
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(commit_log_title(app))
+        .border_style(app.theme.border);
+    app.click_map.content = block.inner(chunks[1]);
+    app.click_map.first_item = list_scroll as usize;
+
+    let list_widget = Paragraph::new(lines).block(block).scroll((list_scroll, 0));
+    frame.render_widget(list_widget, chunks[1]);
+
+    draw_help_bar(frame, app, chunks[2]);
+}
+
+/// Commit log block title: active history search and a `+` while more
+/// pages can still be loaded.
+fn commit_log_title(app: &App) -> String {
+    let more = if app.commit_log_exhausted { "" } else { "+" };
+    let filter = &app.log_query.filter;
+    let scope = if filter.is_empty() {
+        String::new()
+    } else {
+        format!(" — {filter}")
+    };
+    match &app.log_query.search {
+        Some(search) => format!(
+            " Commit Log{scope} — {} ({}{more}) ",
+            search.label(),
+            app.commit_log.len()
+        ),
+        None => format!(" Commit Log{scope} ({}{more}) ", app.commit_log.len()),
+    }
+}
+
+// ── Commit Detail screen ────────────────────────────────────────
+
+fn draw_commit_detail_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
+    let header_lines = app
+        .commit_detail
+        .as_ref()
+        .map(|d| commit_detail_header(d, &app.theme))
+        .unwrap_or_default();
+
+    // Header gets what it needs, but never more than a third of the screen
+    let area = frame.area();
+    let header_height = (header_lines.len() as u16 + 2).min(area.height / 3).max(3);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),             // status bar
+            Constraint::Length(header_height), // commit metadata
+            Constraint::Min(1),                // diff area
+            Constraint::Length(1),             // help bar
+        ])
+        .split(area);
+
+    draw_status_bar(frame, app, state, chunks[0]);
+
+    let title = app
+        .commit_detail
+        .as_ref()
+        .map(|d| format!(" Commit {} ", &d.hash[..d.hash.len().min(12)]))
+        .unwrap_or_else(|| " Commit ".to_string());
+    let header_widget = Paragraph::new(header_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(app.theme.border),
+    );
+    frame.render_widget(header_widget, chunks[1]);
+
+    draw_diff_lines(frame, app, chunks[2], " Changes ".to_string());
+
+    draw_help_bar(frame, app, chunks[3]);
+}
+
+/// Build the metadata pane: parents, author, committer, message, trailers.
+fn commit_detail_header(detail: &CommitDetail, theme: &Theme) -> Vec<Line<'static>> {
+    let label = theme.muted;
+    let mut lines = Vec::new();
+
+    let field = |name: &str, value: String, style: Style| {
+        Line::from(vec![
+            Span::styled(format!("{name:<10}"), label),
+            Span::styled(value, style),
+        ])
+    };
+
+    let parents = if detail.parents.is_empty() {
+        "(root commit)".to_string()
+    } else {
+        detail
+            .parents
+            .iter()
+            .map(|p| p[..p.len().min(12)].to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    lines.push(field("Parents", parents, theme.hash));
+    lines.push(field(
+        "Author",
+        format!(
+            "{} <{}>  {}",
+            detail.author, detail.author_email, detail.author_date
+        ),
+        theme.author,
+    ));
+    if detail.committer != detail.author
+        || detail.committer_email != detail.author_email
+        || detail.committer_date != detail.author_date
+    {
+        lines.push(field(
+            "Committer",
+            format!(
+                "{} <{}>  {}",
+                detail.committer, detail.committer_email, detail.committer_date
+            ),
+            theme.author,
+        ));
+    }
+
+    lines.push(Line::default());
+    for (i, msg_line) in detail.message.lines().enumerate() {
+        let style = if i == 0 {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(format!("    {msg_line}"), style)));
+    }
+
+    if !detail.trailers.is_empty() {
+        lines.push(Line::default());
+        for (key, value) in &detail.trailers {
+            lines.push(Line::from(vec![
+                Span::styled(format!("    {key}: "), theme.trailer),
+                Span::raw(value.clone()),
+            ]));
+        }
+    }
+
+    lines
+}
+
+// ── Timeline screen ─────────────────────────────────────────────
+
+fn draw_timeline_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
+    // The list gets what it needs, but never more than a third of the screen
+    let area = frame.area();
+    let list_height = (app.timeline.len() as u16 + 2).min(area.height / 3).max(3);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),           // status bar
+            Constraint::Length(list_height), // snapshots
+            Constraint::Min(1),              // diff area
+            Constraint::Length(1),           // help bar
+        ])
+        .split(area);
+
+    draw_status_bar(frame, app, state, chunks[0]);
+
+    let theme = &app.theme;
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let compared = app.timeline_compared();
+    let selected_id = app.selected_snapshot().map(|s| s.id);
+    let mut selected_row = 0;
+    // Newest first, like the commit log
+    let lines: Vec<Line> = app
+        .timeline
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(row, snapshot)| {
+            let is_selected = Some(snapshot.id) == selected_id;
+            if is_selected {
+                selected_row = row;
+            }
+            let mark = if app.timeline_mark == Some(snapshot.id) {
+                "* "
+            } else if is_selected {
+                "> "
+            } else {
+                "  "
+            };
+            let head = snapshot
+                .head
+                .as_deref()
+                .map_or("-------", |h| &h[..h.len().min(7)]);
+            let (added, removed) = snapshot.step_totals();
+            let files: Vec<&str> = snapshot.step.iter().map(|(f, _, _)| f.as_str()).collect();
+            let summary = if snapshot.step.is_empty() {
+                "session start".to_string()
+            } else {
+                files.join(", ")
+            };
+            let mut spans = vec![
+                Span::styled(mark.to_string(), theme.muted),
+                Span::styled(format!("#{:<4}", snapshot.id), theme.hash),
+                Span::styled(
+                    format!("{:>9}  ", ago(snapshot.taken.elapsed().as_secs())),
+                    theme.date,
+                ),
+                Span::styled(format!("{}@{head}  ", snapshot.branch), theme.refs),
+                Span::styled(format!("+{added}"), theme.added),
+                Span::raw(" "),
+                Span::styled(format!("-{removed}"), theme.removed),
+                Span::raw("  "),
+            ];
+            let used: usize = spans.iter().map(|s| s.width()).sum();
+            spans.push(Span::styled(
+                truncate_str(&summary, width.saturating_sub(used)),
+                theme.context,
+            ));
+            if is_selected {
+                for span in &mut spans {
+                    span.style = theme.selected;
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    // Keep the selection in view
+    let list_rows = list_height.saturating_sub(2);
+    let list_scroll = (selected_row as u16).saturating_sub(list_rows.saturating_sub(1));
+    let title = format!(" Timeline ({}) ", app.timeline.len());
+    let list = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Commit Log ")
-                .border_style(Style::default().fg(Color::DarkGray)),
+                .title(title)
+                .border_style(app.theme.border),
         )
         .scroll((list_scroll, 0));
-    frame.render_widget(list_widget, chunks[1]);
+    frame.render_widget(list, chunks[1]);
+
+    let title = match compared {
+        Some((Some(older), newer)) => format!(" #{} → #{} ", older.id, newer.id),
+        Some((None, newer)) => format!(" HEAD → #{} ", newer.id),
+        None => " Changes ".to_string(),
+    };
+    draw_diff_lines(frame, app, chunks[2], title);
+
+    draw_help_bar(frame, app, chunks[3]);
+}
+
+// ── Blame screen ────────────────────────────────────────────────
+
+fn draw_blame_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // status bar
+            Constraint::Min(1),    // blame lines
+            Constraint::Length(1), // help bar
+        ])
+        .split(frame.area());
+
+    draw_status_bar(frame, app, state, chunks[0]);
+
+    app.viewport_height = chunks[1].height.saturating_sub(2);
+
+    let Some(view) = app.blame_stack.last() else {
+        draw_help_bar(frame, app, chunks[2]);
+        return;
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let author_width = view
+        .lines
+        .iter()
+        .map(|l| l.author.width())
+        .max()
+        .unwrap_or(8)
+        .min(16);
+    let number_width = view.lines.len().to_string().len();
+
+    let theme = &app.theme;
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, bl) in view.lines.iter().enumerate() {
+        let age = now.saturating_sub(bl.author_time);
+        let (hash, author, age_label) = if bl.is_uncommitted() {
+            ("uncommit".to_string(), "You".to_string(), "now".to_string())
+        } else {
+            (
+                bl.hash[..bl.hash.len().min(8)].to_string(),
+                truncate_str(&bl.author, author_width),
+                format_age(age),
+            )
+        };
+        let age_style = Style::default().fg(theme.age_color(age_bucket(age), bl.is_uncommitted()));
+
+        let mut spans = vec![
+            Span::styled(format!("{hash:<8} "), age_style),
+            Span::styled(pad(author, author_width) + " ", theme.author),
+            Span::styled(format!("{age_label:>4} "), age_style),
+            Span::styled(format!("{:>number_width$} │ ", bl.line_no), theme.muted),
+            Span::styled(bl.content.clone(), theme.context),
+        ];
+        if i == view.selected {
+            for span in &mut spans {
+                span.style = theme.selected;
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+
+    // Keep the cursor in view
+    let list_scroll = (view.selected as u16).saturating_sub(app.viewport_height.saturating_sub(1));
+
+    let rev = view.rev.as_deref().map(|r| &r[..r.len().min(8)]);
+    let title = match rev {
+        Some(rev) => format!(" Blame {} @ {rev} ", view.path),
+        None => format!(" Blame {} ", view.path),
+    };
+    let title = match app.blame_selected() {
+        Some(bl) if !bl.summary.is_empty() && !bl.is_uncommitted() => {
+            format!("{title}— {} ", bl.summary)
+        }
+        _ => title,
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(app.theme.border);
+    app.click_map.content = block.inner(chunks[1]);
+    app.click_map.first_item = list_scroll as usize;
+
+    let blame_widget = Paragraph::new(lines).block(block).scroll((list_scroll, 0));
+    frame.render_widget(blame_widget, chunks[1]);
 
     draw_help_bar(frame, app, chunks[2]);
 }
 
+/// Compact age such as `5m`, `3d`, `2mo`, `1y`.
+fn format_age(secs: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    match secs {
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < 30 * DAY => format!("{}d", s / DAY),
+        s if s < 365 * DAY => format!("{}mo", s / (30 * DAY)),
+        s => format!("{}y", s / (365 * DAY)),
+    }
+}
+
+/// Index into `Theme::age`: day, week, month, year, older.
+fn age_bucket(secs: i64) -> usize {
+    const DAY: i64 = 24 * 60 * 60;
+    match secs {
+        s if s < DAY => 0,
+        s if s < 7 * DAY => 1,
+        s if s < 30 * DAY => 2,
+        s if s < 365 * DAY => 3,
+        _ => 4,
+    }
+}
+
+// ── Commit composer screen ──────────────────────────────────────
+
+fn draw_commit_screen(frame: &mut Frame, app: &mut App, state: &RepoState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // status bar
+            Constraint::Length(3), // options
+            Constraint::Min(3),    // message preview
+            Constraint::Length((state.staged_diff.len() as u16 + 2).clamp(3, 12)), // staged files
+            Constraint::Length(1), // help bar
+        ])
+        .split(frame.area());
+
+    draw_status_bar(frame, app, state, chunks[0]);
+
+    let Some(draft) = &app.commit_draft else {
+        draw_help_bar(frame, app, chunks[4]);
+        return;
+    };
+
+    let theme = &app.theme;
+    let checkbox = |key: &str, label: &str, on: bool| {
+        let mark = if on { "[x]" } else { "[ ]" };
+        let style = if on { theme.enabled } else { theme.muted };
+        vec![
+            Span::styled(format!(" {mark} "), style),
+            Span::styled(format!("{key}: "), theme.hash),
+            Span::styled(format!("{label}   "), style),
+        ]
+    };
+    let options = [
+        (Action::ToggleAmend, "amend", draft.options.amend),
+        (Action::ToggleSignoff, "sign-off", draft.options.signoff),
+        (Action::ToggleNoVerify, "no-verify", draft.options.no_verify),
+    ];
+    let mut option_spans = Vec::new();
+    let mut buttons = Vec::new();
+    for (action, label, on) in options {
+        let spans = checkbox(&first_key(&app.keymap, Screen::Commit, action), label, on);
+        let width: usize = spans.iter().map(Span::width).sum();
+        buttons.push((width, Some(action)));
+        option_spans.extend(spans);
+    }
+    let options_widget = Paragraph::new(Line::from(option_spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Options ")
+            .border_style(app.theme.border),
+    );
+    frame.render_widget(options_widget, chunks[1]);
+    let options_row = Rect {
+        x: chunks[1].x + 1,
+        y: chunks[1].y + 1,
+        ..chunks[1]
+    };
+    add_buttons(&mut app.click_map, options_row, &buttons);
+
+    let message_lines: Vec<Line> = if draft.message.is_empty() {
+        vec![Line::from(Span::styled(
+            "(no message yet — press e to edit)",
+            theme.muted,
+        ))]
+    } else {
+        draft
+            .message
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                let style = if i == 0 {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(l.to_string(), style))
+            })
+            .collect()
+    };
+    let title = if draft.options.amend {
+        " Message (amending HEAD) "
+    } else {
+        " Message "
+    };
+    let message_widget = Paragraph::new(message_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(app.theme.border),
+    );
+    frame.render_widget(message_widget, chunks[2]);
+
+    let staged_lines: Vec<Line> = if state.staged_diff.is_empty() {
+        vec![Line::from(Span::styled("(nothing staged)", theme.muted))]
+    } else {
+        state
+            .staged_diff
+            .iter()
+            .map(|fd| {
+                Line::from(vec![
+                    Span::raw(format!("{} ", fd.filename)),
+                    Span::styled(format!("+{}", fd.added), theme.added),
+                    Span::raw(" "),
+                    Span::styled(format!("-{}", fd.removed), theme.removed),
+                ])
+            })
+            .collect()
+    };
+    let staged_widget = Paragraph::new(staged_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Staged ({} files) ", state.staged_diff.len()))
+            .border_style(app.theme.border),
+    );
+    frame.render_widget(staged_widget, chunks[3]);
+
+    draw_help_bar(frame, app, chunks[4]);
+}
+
 // ── Shared widgets ──────────────────────────────────────────────
 
-fn draw_status_bar(frame: &mut Frame, state: &RepoState, area: ratatui::layout::Rect) {
+fn draw_status_bar(frame: &mut Frame, app: &mut App, state: &RepoState, area: Rect) {
     let branch = &state.branch;
     let short_sha = state
         .last_commit_hash
@@ -206,76 +1006,449 @@ fn draw_status_bar(frame: &mut Frame, state: &RepoState, area: ratatui::layout::
         .last_commit_message
         .as_deref()
         .unwrap_or("(no commits)");
-    let elapsed = state.refreshed_at.elapsed().as_secs();
-    let ago = if elapsed == 0 {
-        String::from("just now")
-    } else if elapsed < 60 {
-        format!("{elapsed}s ago")
-    } else {
-        format!("{}m ago", elapsed / 60)
-    };
-    let status_text = format!(
-        " {branch} | {short_sha} {commit_msg} | {} staged, {} unstaged  {ago}",
-        state.staged_count, state.unstaged_count,
-    );
+    let ago = ago(state.refreshed_at.elapsed().as_secs());
+    // Branch opens the log, the counts toggle staged/unstaged and the
+    // path filter opens its prompt
+    let mut segments = vec![
+        (format!(" {branch} "), Some(Action::CommitLog)),
+        (format!("| {short_sha} {commit_msg} "), None),
+        (
+            format!(
+                "| {} staged, {} unstaged",
+                state.staged_count, state.unstaged_count
+            ),
+            Some(Action::ToggleView),
+        ),
+    ];
+    if let Some(base) = &app.base {
+        segments.push((format!(" | base: {base}"), Some(Action::CommandPrompt)));
+    }
+    if !app.path_filter.is_empty() {
+        segments.push((
+            format!(" | paths: {}", app.path_filter),
+            Some(Action::EditPathFilter),
+        ));
+    }
+    match app.follow {
+        Follow::On => segments.push((" | FOLLOW".into(), Some(Action::ToggleFollow))),
+        Follow::Paused => segments.push((" | follow paused".into(), Some(Action::ToggleFollow))),
+        Follow::Off => {}
+    }
+    for (name, running, last) in app.hooks.iter() {
+        let outcome = match (running, last) {
+            (true, _) => "…".to_string(),
+            (false, Some(result)) if result.success => "✓".to_string(),
+            (false, Some(result)) if result.timed_out.is_some() => "✗ timeout".to_string(),
+            (false, Some(result)) => match result.code {
+                Some(code) => format!("✗ {code}"),
+                None => "✗".to_string(),
+            },
+            (false, None) => continue,
+        };
+        segments.push((format!(" | {name} {outcome}"), Some(Action::ToggleHooks)));
+    }
+    if app.check.enabled() {
+        let name = app.check.name();
+        let outcome = match (app.check.started(), &app.check.last) {
+            (Some(started), _) => format!("… {}", duration(started.elapsed())),
+            (None, Some(result)) if result.success => {
+                format!("✓ {}", duration(result.duration))
+            }
+            (None, Some(result)) => format!("✗ {}", duration(result.duration)),
+            (None, None) => "-".to_string(),
+        };
+        segments.push((format!(" | {name} {outcome}"), Some(Action::ToggleChecks)));
+    }
+    segments.push((format!("  {ago}"), None));
+    let widths: Vec<(usize, Option<Action>)> = segments
+        .iter()
+        .map(|(text, action)| (Span::raw(text.as_str()).width(), *action))
+        .collect();
+    add_buttons(&mut app.click_map, area, &widths);
+
+    let status_text: String = segments.into_iter().map(|(text, _)| text).collect();
     let status_bar = Paragraph::new(Line::from(vec![Span::styled(
         status_text,
-        Style::default()
-            .fg(Color::Black)
-            .bg(Color::Cyan)
-            .add_modifier(Modifier::BOLD),
+        app.theme.status_bar,
     )]))
-    .style(Style::default().bg(Color::Cyan));
+    .style(app.theme.status_bar);
     frame.render_widget(status_bar, area);
 }
 
-fn draw_help_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+/// A run time such as `850ms` or `12.4s`.
+fn duration(d: Duration) -> String {
+    if d < Duration::from_secs(1) {
+        format!("{}ms", d.as_millis())
+    } else {
+        format!("{:.1}s", d.as_secs_f64())
+    }
+}
+
+/// How long ago something happened, e.g. `12s ago`.
+fn ago(secs: u64) -> String {
+    if secs == 0 {
+        String::from("just now")
+    } else if secs < 60 {
+        format!("{secs}s ago")
+    } else {
+        format!("{}m ago", secs / 60)
+    }
+}
+
+/// Register clickable segments laid out left to right from `area.x`.
+/// Each entry is a segment's display width and its action, if any.
+fn add_buttons(click_map: &mut ClickMap, area: Rect, segments: &[(usize, Option<Action>)]) {
+    let mut x = area.x;
+    for &(width, action) in segments {
+        let width = (width as u16).min((area.x + area.width).saturating_sub(x));
+        if let Some(action) = action {
+            let rect = Rect {
+                x,
+                y: area.y,
+                width,
+                height: 1,
+            };
+            click_map.buttons.push((rect, action));
+        }
+        x += width;
+    }
+}
+
+fn draw_help_bar(frame: &mut Frame, app: &mut App, area: Rect) {
     let help_text = match app.input_mode {
         InputMode::Search => {
-            let prefix = if app.search.forward { "/" } else { "?" };
-            format!("{prefix}{}█", app.search.query)
+            let search = &app.search;
+            let prefix = if search.forward { "/" } else { "?" };
+            let mode = if search.literal { "literal" } else { "regex" };
+            let views = if search.all_views && app.screen == Screen::Diff {
+                ", both views"
+            } else {
+                ""
+            };
+            let status = match &search.error {
+                Some(err) => err.clone(),
+                None if search.query.is_empty() => String::new(),
+                None => format!("{} matches", app.search_match_count()),
+            };
+            format!(
+                "{prefix}{}█   [{mode}, {}{views}] {status}   (Tab: scope, C-r: regex, C-a: both views, +: -: f: prefixes)",
+                search.query,
+                search.scope.label(),
+            )
         }
+        InputMode::HistorySearch => format!(
+            "history: {}█   (text = message, author:NAME, code:STRING)",
+            app.history_query
+        ),
+        InputMode::LogFilter => match &app.message {
+            Some(err) => format!("filter: {}█   {err}", app.log_filter_input),
+            None => format!(
+                "filter: {}█   (path= author= since= until= branch= all)",
+                app.log_filter_input
+            ),
+        },
+        InputMode::PathFilter => match &app.message {
+            Some(err) => format!("paths: {}█   {err}", app.path_filter_input),
+            None => format!(
+                "paths: {}█   (dirs or globs, space-separated; empty shows everything)",
+                app.path_filter_input
+            ),
+        },
+        InputMode::Command => match &app.completion {
+            Some(c) if c.candidates.len() > 1 => {
+                let list: Vec<String> = c
+                    .candidates
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cand)| {
+                        if i == c.index {
+                            format!("[{cand}]")
+                        } else {
+                            cand.clone()
+                        }
+                    })
+                    .collect();
+                format!(":{}█   {}", app.command_input, list.join(" "))
+            }
+            _ => format!(
+                ":{}█   (base REF, filter PATHS, log FILTERS, w FILE, q; Tab completes)",
+                app.command_input
+            ),
+        },
         InputMode::Normal => {
-            if app.search.active && !app.search.matches.is_empty() {
-                let total = app.search.matches.len();
-                let current = app.search.current_match + 1;
+            let keys = |action| first_key(&app.keymap, app.screen, action);
+            if let Some(msg) = &app.message {
+                format!(" {msg}")
+            } else if !app.pending_keys.is_empty() || app.pending_count.is_some() {
+                let count = app.pending_count.map(|n| n.to_string()).unwrap_or_default();
+                let typed: String = app.pending_keys.iter().map(|k| k.to_string()).collect();
+                format!(" {count}{typed}…")
+            } else if let Some((current, total)) = app.search_position() {
+                let clear = match app.screen {
+                    Screen::Diff => Action::ClearSearch,
+                    _ => Action::Back,
+                };
                 format!(
-                    " [{current}/{total}] \"{}\"  n/N: next/prev | Esc: clear",
-                    app.search.query
+                    " [{current}/{total}] \"{}\"  {}/{}: next/prev | {}: clear",
+                    app.search.query,
+                    keys(Action::SearchNext),
+                    keys(Action::SearchPrev),
+                    keys(clear),
                 )
             } else {
-                match app.screen {
-                    Screen::Diff => {
-                        " q: quit | Tab: staged/unstaged | j/k: scroll | ]/[: file | Space: fold | C/E: all | /: search | d: pager | l: log ".to_string()
-                    }
-                    Screen::CommitLog => {
-                        " q/Esc: back | j/k: navigate | Enter/d: view in pager | /: search ".to_string()
+                let hints = help_hints(&app.keymap, app.screen);
+                let mut segments = vec![(1, None)];
+                for (i, (text, action)) in hints.iter().enumerate() {
+                    if i > 0 {
+                        segments.push((3, None));
                     }
+                    segments.push((Span::raw(text.as_str()).width(), Some(*action)));
                 }
+                add_buttons(&mut app.click_map, area, &segments);
+                let texts: Vec<&str> = hints.iter().map(|(text, _)| text.as_str()).collect();
+                format!(" {} ", texts.join(" | "))
             }
         }
     };
 
-    let style = if app.input_mode == InputMode::Search {
-        Style::default().fg(Color::White).bg(Color::DarkGray)
+    let style = if app.input_mode != InputMode::Normal {
+        app.theme.prompt
     } else if app.search.active {
-        Style::default().fg(Color::Yellow)
+        app.theme.help_active
     } else {
-        Style::default().fg(Color::DarkGray)
+        app.theme.help
     };
 
     let help_bar = Paragraph::new(Line::from(Span::styled(help_text, style)));
     frame.render_widget(help_bar, area);
 }
 
+/// Help bar entries per screen: the actions of each hint and its label.
+fn screen_hints(screen: Screen) -> &'static [(&'static [Action], &'static str)] {
+    use Action::*;
+    match screen {
+        Screen::Diff => &[
+            (&[Quit], "quit"),
+            (&[ToggleView], "staged/unstaged"),
+            (&[ScrollDown, ScrollUp], "scroll"),
+            (&[NextFile, PrevFile], "file"),
+            (&[ToggleFold], "fold"),
+            (&[FoldAll, UnfoldAll], "all"),
+            (&[SearchForward], "search"),
+            (&[VisualSelect, Yank], "select/yank"),
+            (&[Pager], "pager"),
+            (&[ComposeCommit], "commit"),
+            (&[Blame], "blame"),
+            (&[CommitLog, FileLog], "log/file log"),
+            (&[Timeline], "timeline"),
+            (&[ToggleSidebar, FocusPane], "tree/focus"),
+            (&[EditPathFilter], "paths"),
+        ],
+        Screen::CommitLog => &[
+            (&[Back], "back"),
+            (&[ScrollDown, ScrollUp], "navigate"),
+            (&[Open], "open"),
+            (&[Pager], "pager"),
+            (&[SearchForward], "search"),
+            (&[HistorySearch], "history"),
+            (
+                &[ToggleFullHash, ToggleAbsoluteDate, ToggleRefs, ToggleGraph],
+                "columns",
+            ),
+            (&[ToggleAllRefs], "all refs"),
+            (&[EditFilter], "filter"),
+        ],
+        Screen::CommitDetail => &[
+            (&[Back], "back"),
+            (&[ScrollDown, ScrollUp], "scroll"),
+            (&[NextFile, PrevFile], "file"),
+            (&[ToggleFold], "fold"),
+            (&[FoldAll, UnfoldAll], "all"),
+            (&[SearchForward], "search"),
+            (&[VisualSelect, Yank], "select/yank"),
+            (&[Pager], "pager"),
+        ],
+        Screen::Blame => &[
+            (&[Back], "back"),
+            (&[ScrollDown, ScrollUp], "move"),
+            (&[Open], "commit"),
+            (&[BlameParent], "blame parent"),
+            (&[BlameBack], "back"),
+            (&[FileLog], "file log"),
+        ],
+        Screen::Timeline => &[
+            (&[Back], "back"),
+            (&[OlderSnapshot, NewerSnapshot], "older/newer"),
+            (&[MarkSnapshot], "mark"),
+            (&[ScrollDown, ScrollUp], "scroll"),
+            (&[NextFile, PrevFile], "file"),
+            (&[ToggleFold], "fold"),
+            (&[SearchForward], "search"),
+            (&[VisualSelect, Yank], "select/yank"),
+            (&[Pager], "pager"),
+        ],
+        Screen::Commit => &[
+            (&[Back], "cancel"),
+            (&[EditMessage], "edit message"),
+            (&[ToggleAmend, ToggleSignoff, ToggleNoVerify], "options"),
+            (&[Commit], "commit"),
+        ],
+    }
+}
+
+/// First key bound to `action`, or an empty string if it is unbound.
+fn first_key(keymap: &KeyMap, screen: Screen, action: Action) -> String {
+    keymap
+        .keys_for(screen, action)
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+}
+
+/// Help bar hints built from the active keymap, e.g. `j/k: scroll`, each
+/// with the action a click on it runs. Hints whose actions are all unbound
+/// are left out.
+fn help_hints(keymap: &KeyMap, screen: Screen) -> Vec<(String, Action)> {
+    // Help first, so it survives truncation on narrow terminals
+    let help: &[(&[Action], &str)] = &[(&[Action::Help], "help")];
+    help.iter()
+        .chain(screen_hints(screen))
+        .filter_map(|(actions, label)| {
+            let bound: Vec<(String, Action)> = actions
+                .iter()
+                .map(|&a| (first_key(keymap, screen, a), a))
+                .filter(|(k, _)| !k.is_empty())
+                .collect();
+            let &(_, first) = bound.first()?;
+            let keys: Vec<&str> = bound.iter().map(|(k, _)| k.as_str()).collect();
+            Some((format!("{}: {label}", keys.join("/")), first))
+        })
+        .collect()
+}
+
+/// Centered modal box; the body is wrapped and cut off if it doesn't fit.
+fn draw_popup(frame: &mut Frame, theme: &Theme, popup: &Popup) {
+    let area = frame.area();
+    let width = (area.width * 4 / 5).max(20).min(area.width);
+    let height = (popup.body.lines().count() as u16 + 3)
+        .min(area.height * 4 / 5)
+        .max(3)
+        .min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut lines: Vec<Line> = popup
+        .body
+        .lines()
+        .map(|l| Line::raw(l.to_string()))
+        .collect();
+    lines.push(Line::from(Span::styled(
+        "press any key to dismiss",
+        theme.muted,
+    )));
+    let widget = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(popup.title.clone())
+            .border_style(theme.error),
+    );
+    frame.render_widget(Clear, rect);
+    frame.render_widget(widget, rect);
+}
+
+/// Key bindings of the current screen, grouped by category, drawn over
+/// almost the whole terminal with a filter line at the bottom.
+fn draw_help_overlay(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+    let rect = Rect {
+        x: area.x + area.width.min(2),
+        y: area.y + area.height.min(1),
+        width: area.width.saturating_sub(4),
+        height: area.height.saturating_sub(2),
+    };
+    let theme = &app.theme;
+    let Some(help) = app.help.as_mut() else {
+        return;
+    };
+
+    let sections = app.keymap.help(app.screen, &help.filter);
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, rows)| rows)
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Line> = Vec::new();
+    for (category, rows) in &sections {
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::from(Span::styled(*category, theme.diff_header)));
+        for (keys, description) in rows {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {keys:<key_width$}  "), theme.hash),
+                Span::raw(*description),
+            ]));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("no matching keys", theme.muted)));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Keys — {} ", screen_title(app.screen)))
+        .border_style(theme.border);
+    let inner = block.inner(rect);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let max_scroll = (lines.len() as u16).saturating_sub(chunks[0].height);
+    help.scroll = help.scroll.min(max_scroll);
+
+    let footer = if help.editing {
+        Span::styled(format!("/{}█", help.filter), theme.prompt)
+    } else if !help.filter.is_empty() {
+        Span::styled(
+            format!(" filter: {}   /: edit | Esc: clear | q: close", help.filter),
+            theme.help_active,
+        )
+    } else {
+        Span::styled(" j/k: scroll | /: filter | q/Esc: close", theme.help)
+    };
+
+    frame.render_widget(Clear, rect);
+    frame.render_widget(block, rect);
+    frame.render_widget(Paragraph::new(lines).scroll((help.scroll, 0)), chunks[0]);
+    frame.render_widget(Paragraph::new(Line::from(footer)), chunks[1]);
+}
+
+fn screen_title(screen: Screen) -> &'static str {
+    match screen {
+        Screen::Diff => "Diff",
+        Screen::CommitLog => "Commit log",
+        Screen::CommitDetail => "Commit detail",
+        Screen::Blame => "Blame",
+        Screen::Commit => "Commit",
+        Screen::Timeline => "Timeline",
+    }
+}
+
 // ── Diff line styling with search highlight ─────────────────────
 
 /// Map a `DiffLine` to a coloured `Line`, with search matches highlighted.
 fn highlight_diff_line(
     dl: &DiffLine,
-    line_idx: usize,
-    search: &SearchState,
+    line_matches: &[(usize, usize, bool)],
+    changed: bool,
     collapsed: &std::collections::HashSet<String>,
+    theme: &Theme,
     term_width: usize,
 ) -> Line<'static> {
     // Special rendering for file section headers
@@ -285,121 +1458,153 @@ fn highlight_diff_line(
         removed,
     } = dl
     {
-        return render_file_header(filename, *added, *removed, collapsed, term_width);
+        return render_file_header(
+            filename,
+            (*added, *removed),
+            line_matches,
+            changed,
+            collapsed,
+            theme,
+            term_width,
+        );
     }
 
     let base_style = match dl {
         DiffLine::FileHeader { .. } => unreachable!(),
-        DiffLine::Header(_) => Style::default()
-            .fg(Color::Yellow)
-            .add_modifier(Modifier::BOLD),
-        DiffLine::Hunk(_) => Style::default().fg(Color::Cyan),
-        DiffLine::Added(_) => Style::default().fg(Color::Green),
-        DiffLine::Removed(_) => Style::default().fg(Color::Red),
-        DiffLine::Context(_) => Style::default(),
+        DiffLine::Header(_) => theme.diff_header,
+        DiffLine::Hunk(_) => theme.hunk,
+        DiffLine::Added(_) => theme.added,
+        DiffLine::Removed(_) => theme.removed,
+        DiffLine::Context(_) => theme.context,
     };
 
-    let text = dl.text().to_string();
-
-    if !search.active || search.query.is_empty() || search.matches.is_empty() {
-        return Line::from(Span::styled(text, base_style));
-    }
-
-    // Collect matches for this line
-    let line_matches: Vec<(usize, usize, bool)> = search
-        .matches
-        .iter()
-        .enumerate()
-        .filter(|(_, (li, _, _))| *li == line_idx)
-        .map(|(match_idx, (_, start, end))| {
-            let is_current = match_idx == search.current_match;
-            (*start, *end, is_current)
-        })
-        .collect();
-
-    if line_matches.is_empty() {
-        return Line::from(Span::styled(text, base_style));
-    }
+    Line::from(highlight_matches(
+        dl.text(),
+        line_matches,
+        base_style,
+        theme,
+    ))
+}
 
+/// Split `text` into spans with `(start, end, is_current)` byte ranges
+/// highlighted.
+fn highlight_matches(
+    text: &str,
+    matches: &[(usize, usize, bool)],
+    base_style: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
     let mut pos = 0;
-    for (start, end, is_current) in &line_matches {
+    for (start, end, is_current) in matches {
         let start = (*start).min(text.len());
         let end = (*end).min(text.len());
         if pos < start {
             spans.push(Span::styled(text[pos..start].to_string(), base_style));
         }
         let highlight_style = if *is_current {
-            Style::default().bg(Color::Red).fg(Color::White)
+            theme.search_current
         } else {
-            Style::default().bg(Color::Yellow).fg(Color::Black)
+            theme.search_match
         };
         spans.push(Span::styled(text[start..end].to_string(), highlight_style));
         pos = end;
     }
-    if pos < text.len() {
+    if pos < text.len() || spans.is_empty() {
         spans.push(Span::styled(text[pos..].to_string(), base_style));
     }
+    spans
+}
 
-    Line::from(spans)
+/// Highlight for a change made at `at`: `changed`, then `changed_fading`
+/// for the second half of `ttl`, then nothing.
+fn change_style(theme: &Theme, at: Instant, ttl: Duration) -> Option<Style> {
+    let age = at.elapsed();
+    if age >= ttl {
+        None
+    } else if age < ttl / 2 {
+        Some(theme.changed)
+    } else {
+        Some(theme.changed_fading)
+    }
 }
 
 /// Render a file section header: `▾/▸ filename   +N -M` with full-width bar.
 fn render_file_header(
     filename: &str,
-    added: usize,
-    removed: usize,
+    (added, removed): (usize, usize),
+    matches: &[(usize, usize, bool)],
+    changed: bool,
     collapsed: &std::collections::HashSet<String>,
+    theme: &Theme,
     term_width: usize,
 ) -> Line<'static> {
     let is_collapsed = collapsed.contains(filename);
-    let arrow = if is_collapsed { "▸ " } else { "▾ " };
+    // A dot marks files a recent refresh touched
+    let arrow = match (is_collapsed, changed) {
+        (true, false) => "▸ ",
+        (false, false) => "▾ ",
+        (true, true) => "▸ ● ",
+        (false, true) => "▾ ● ",
+    };
     let stats = format!("+{added} -{removed}");
 
-    let bg = Style::default()
-        .bg(Color::DarkGray)
-        .fg(Color::White)
-        .add_modifier(Modifier::BOLD);
+    let bg = theme.file_header;
+    // Stats keep the header bar but take the added/removed foreground
+    let stat = |s: Style| match s.fg {
+        Some(fg) => bg.fg(fg),
+        None => bg,
+    };
 
     // Calculate padding between filename and stats
-    let content_len = arrow.len() + filename.len() + stats.len() + 2; // +2 for spaces around stats
+    let content_len = arrow.chars().count() + filename.len() + stats.len() + 2; // +2 for spaces around stats
     let padding = if term_width > content_len {
         term_width - content_len
     } else {
         1
     };
 
-    Line::from(vec![
-        Span::styled(arrow.to_string(), bg),
-        Span::styled(filename.to_string(), bg),
+    let mut spans = vec![Span::styled(arrow.to_string(), bg)];
+    spans.extend(highlight_matches(filename, matches, bg, theme));
+    spans.extend([
         Span::styled(" ".repeat(padding), bg),
-        Span::styled(
-            format!("+{added}"),
-            Style::default()
-                .bg(Color::DarkGray)
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        ),
+        Span::styled(format!("+{added}"), stat(theme.added)),
         Span::styled(" ".to_string(), bg),
-        Span::styled(
-            format!("-{removed}"),
-            Style::default()
-                .bg(Color::DarkGray)
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-        ),
-    ])
+        Span::styled(format!("-{removed}"), stat(theme.removed)),
+    ]);
+    Line::from(spans)
 }
 
 // ── Helpers ─────────────────────────────────────────────────────
 
-/// Truncate a string to `max_len` characters, appending "..." if truncated.
-fn truncate_str(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else if max_len <= 3 {
-        s[..max_len].to_string()
+/// Truncate a string to `max_width` terminal columns, appending "..." if
+/// truncated.
+fn truncate_str(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    let keep = if max_width <= 3 {
+        max_width
     } else {
-        format!("{}...", &s[..max_len - 3])
+        max_width - 3
+    };
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        used += c.width().unwrap_or(0);
+        if used > keep {
+            break;
+        }
+        out.push(c);
+    }
+    if max_width > 3 {
+        out.push_str("...");
     }
+    out
+}
+
+/// `s` padded with spaces to `width` terminal columns.
+fn pad(s: String, width: usize) -> String {
+    let spaces = width.saturating_sub(s.width());
+    s + &" ".repeat(spaces)
 }
@@ -1,21 +1,51 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
-use std::time::Duration;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 
 use anyhow::Result;
 use ignore::gitignore::Gitignore;
-use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
+use notify::{PollWatcher, RecommendedWatcher, Watcher};
+use notify_debouncer_mini::{new_debouncer_opt, DebouncedEventKind, Debouncer};
 
+use crate::config::{Settings, WatcherMode};
 use crate::event::AppEvent;
+use crate::pathspec::PathFilter;
 
-/// Start the filesystem watcher in its own thread.
-///
-/// Returns a handle that keeps the watcher alive — dropping it stops watching.
+/// Keeps the watcher alive — dropping it stops watching.
+#[allow(dead_code)] // never read, only held
+pub enum WatcherHandle {
+    Native(Debouncer<RecommendedWatcher>),
+    Poll(Debouncer<PollWatcher>),
+}
+
+/// Start the filesystem watcher selected by `settings.watcher`. Working
+/// tree changes outside `filter` are not reported.
 pub fn spawn(
     repo: &Path,
-    debounce_ms: u64,
+    settings: &Settings,
+    filter: Arc<RwLock<PathFilter>>,
+    tx: Sender<AppEvent>,
+) -> Result<WatcherHandle> {
+    match settings.watcher {
+        WatcherMode::Native => {
+            spawn_with::<RecommendedWatcher>(repo, settings, filter, tx).map(WatcherHandle::Native)
+        }
+        WatcherMode::Poll => {
+            spawn_with::<PollWatcher>(repo, settings, filter, tx).map(WatcherHandle::Poll)
+        }
+    }
+}
+
+/// Start a debounced watcher of type `W` in its own thread.
+fn spawn_with<W: Watcher>(
+    repo: &Path,
+    settings: &Settings,
+    filter: Arc<RwLock<PathFilter>>,
     tx: Sender<AppEvent>,
-) -> Result<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>> {
+) -> Result<Debouncer<W>> {
     let repo_path = repo.to_path_buf();
     let git_dir = repo.join(".git");
 
@@ -23,23 +53,48 @@ pub fn spawn(
     let gitignore_path = repo.join(".gitignore");
     let (gitignore, _) = Gitignore::new(&gitignore_path);
 
-    let mut debouncer = new_debouncer(
-        Duration::from_millis(debounce_ms),
+    let config = notify_debouncer_mini::Config::default()
+        .with_timeout(Duration::from_millis(settings.debounce_ms))
+        .with_notify_config(
+            notify::Config::default()
+                .with_poll_interval(Duration::from_millis(settings.poll_interval_ms)),
+        );
+
+    // Modification time and size of every path reported so far
+    let mut seen = HashMap::new();
+
+    let mut debouncer = new_debouncer_opt::<_, W>(
+        config,
         move |res: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
             let events = match res {
                 Ok(evts) => evts,
                 Err(_) => return,
             };
 
+            let Ok(filter) = filter.read() else {
+                return;
+            };
+            // One FsChange per debounce batch, listing the files in it
+            let mut notify = false;
+            let mut changed = Vec::new();
             for event in &events {
-                if event.kind != DebouncedEventKind::Any {
+                if event.kind != DebouncedEventKind::Any
+                    || !should_notify(&event.path, &repo_path, &git_dir, &gitignore, &filter)
+                    || !modified_since_seen(&event.path, &mut seen)
+                {
                     continue;
                 }
-                if should_notify(&event.path, &repo_path, &git_dir, &gitignore) {
-                    let _ = tx.send(AppEvent::FsChange);
-                    return; // one FsChange per debounce batch is enough
+                notify = true;
+                if let Some(file) = changed_file(&event.path, &repo_path, &git_dir) {
+                    changed.push(file);
                 }
             }
+            if notify {
+                // Newest first: usually the file an editor just saved
+                changed.sort_by_key(|(modified, _)| Reverse(*modified));
+                let paths = changed.into_iter().map(|(_, path)| path).collect();
+                let _ = tx.send(AppEvent::FsChange(paths));
+            }
         },
     )?;
 
@@ -51,22 +106,52 @@ pub fn spawn(
 }
 
 /// Decide whether a filesystem event path should trigger a refresh.
-fn should_notify(path: &Path, repo: &Path, git_dir: &PathBuf, gitignore: &Gitignore) -> bool {
+fn should_notify(
+    path: &Path,
+    repo: &Path,
+    git_dir: &PathBuf,
+    gitignore: &Gitignore,
+    filter: &PathFilter,
+) -> bool {
     // Inside .git/ — only care about specific paths that indicate state changes
     if path.starts_with(git_dir) {
         return is_interesting_git_path(path, git_dir);
     }
 
-    // Working tree file — check gitignore
+    // Working tree file — check gitignore and the path filter
     if let Ok(relative) = path.strip_prefix(repo) {
         let is_dir = path.metadata().map(|m| m.is_dir()).unwrap_or(false);
-        return !gitignore.matched(relative, is_dir).is_ignore();
+        return !gitignore.matched(relative, is_dir).is_ignore() && filter.matches(relative);
     }
 
     // Path outside repo — ignore
     false
 }
 
+/// Whether `path` changed since its last event, remembering its current
+/// modification time and size. Native watchers report files being opened,
+/// so without this every refresh would trigger the next one by reading
+/// the index and the changed files. The size catches a second write within
+/// one tick of a coarse mtime. Deleted paths always count as changed.
+fn modified_since_seen(path: &Path, seen: &mut HashMap<PathBuf, (SystemTime, u64)>) -> bool {
+    let Ok(stamp) = path.metadata().and_then(|m| Ok((m.modified()?, m.len()))) else {
+        seen.remove(path);
+        return true;
+    };
+    seen.insert(path.to_path_buf(), stamp) != Some(stamp)
+}
+
+/// Modification time and repository-relative path of a changed working
+/// tree file. Directories, deleted files and `.git/` yield `None`.
+fn changed_file(path: &Path, repo: &Path, git_dir: &Path) -> Option<(SystemTime, PathBuf)> {
+    if path.starts_with(git_dir) {
+        return None;
+    }
+    let meta = path.metadata().ok().filter(|m| m.is_file())?;
+    let relative = path.strip_prefix(repo).ok()?;
+    Some((meta.modified().ok()?, relative.to_path_buf()))
+}
+
 /// Within `.git/`, only a few paths signal meaningful state changes.
 fn is_interesting_git_path(path: &Path, git_dir: &PathBuf) -> bool {
     if let Ok(relative) = path.strip_prefix(git_dir) {
@@ -84,3 +169,35 @@ fn is_interesting_git_path(path: &Path, git_dir: &PathBuf) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+
+    #[test]
+    fn only_modifications_count() {
+        let dir = std::env::temp_dir().join(format!("git-monitor-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("f");
+        fs::write(&path, "a").unwrap();
+        let mtime = path.metadata().unwrap().modified().unwrap();
+        let mut seen = HashMap::new();
+
+        assert!(modified_since_seen(&path, &mut seen));
+        // Opened or read, but not written
+        assert!(!modified_since_seen(&path, &mut seen));
+        // Written again within the same mtime tick
+        fs::write(&path, "ab").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        assert!(modified_since_seen(&path, &mut seen));
+        fs::remove_file(&path).unwrap();
+        assert!(modified_since_seen(&path, &mut seen));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}